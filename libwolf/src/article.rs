@@ -0,0 +1,463 @@
+use crate::{font::Font, gr::Pic, surface::Surface, wl6_igrab::GraphicNum};
+
+/// One piece of an article page, in the order the help viewer handles them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Element {
+    /// Words and the spaces between them, wrapped at the margins when drawn.
+    /// Characters are the bytes of the chunk, which only uses code page 437.
+    Text(String),
+    NewLine,
+    /// `\t`, moves to the next multiple of 8 pixels
+    Tab,
+    /// `^>`, moves to the middle of the screen
+    Center,
+    /// `^Cxx`, changes the text colour
+    Color(u8),
+    /// `^Gy,x,pic`, text flows around the pic
+    Pic {
+        x: usize,
+        y: usize,
+        pic: usize,
+    },
+    /// `^Ty,x,pic,delay`, shown after `delay` tics in the end art
+    TimedPic {
+        x: usize,
+        y: usize,
+        pic: usize,
+        delay: usize,
+    },
+    /// `^By,x,width,height`, clears a box that text flows around like a pic
+    Bar {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// `^Ly,x`, moves the cursor, snapped to a text row
+    Locate {
+        x: usize,
+        y: usize,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Page {
+    pub elements: Vec<Element>,
+}
+
+pub const BACKCOLOR: u8 = 0x11;
+pub const FONTHEIGHT: usize = 10;
+pub const TOPMARGIN: usize = 16;
+pub const BOTTOMMARGIN: usize = 32;
+pub const LEFTMARGIN: usize = 16;
+pub const RIGHTMARGIN: usize = 16;
+pub const PICMARGIN: usize = 8;
+pub const TEXTROWS: usize = (200 - TOPMARGIN - BOTTOMMARGIN) / FONTHEIGHT;
+pub const SPACEWIDTH: usize = 7;
+const SCREENPIXWIDTH: usize = 320;
+const SCREENMID: usize = SCREENPIXWIDTH / 2;
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<u8> {
+        let c = self.text.get(self.pos).copied();
+        self.pos += 1;
+        c
+    }
+
+    /// Skips to the first digit, then reads a decimal number
+    fn number(&mut self) -> usize {
+        while self.pos < self.text.len() && !self.text[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+
+        let mut value = 0usize;
+        while let Some(digit) = self.text.get(self.pos).filter(|c| c.is_ascii_digit()) {
+            value = value
+                .saturating_mul(10)
+                .saturating_add((digit - b'0') as usize);
+            self.pos += 1;
+        }
+        value
+    }
+
+    fn hex_digit(&mut self) -> u8 {
+        self.next()
+            .and_then(|c| (c as char).to_digit(16))
+            .unwrap_or_default() as u8
+    }
+
+    fn rip_to_eol(&mut self) {
+        while let Some(c) = self.next() {
+            if c == b'\n' {
+                break;
+            }
+        }
+    }
+}
+
+/// Splits an article chunk such as `T_HELPART` or `T_ENDART1` into pages.
+/// Every page starts with `^P` and the article stops at `^E`.
+pub fn parse_article(text: &[u8]) -> Vec<Page> {
+    let mut parser = Parser { text, pos: 0 };
+    let mut pages: Vec<Page> = Vec::new();
+    let mut run = String::new();
+
+    fn flush(run: &mut String, pages: &mut [Page]) {
+        if let Some(page) = pages.last_mut().filter(|_| !run.is_empty()) {
+            page.elements.push(Element::Text(std::mem::take(run)));
+        }
+        run.clear();
+    }
+
+    while let Some(c) = parser.next() {
+        let element = match c {
+            b'^' => {
+                flush(&mut run, &mut pages);
+
+                match parser.next().map(|c| c.to_ascii_uppercase()) {
+                    Some(b'P') => {
+                        pages.push(Page::default());
+                        parser.rip_to_eol();
+                        continue;
+                    }
+                    Some(b'E') | None => break,
+                    Some(b'C') => Element::Color(parser.hex_digit() * 16 + parser.hex_digit()),
+                    Some(b'>') => Element::Center,
+                    Some(b';') => {
+                        parser.rip_to_eol();
+                        continue;
+                    }
+                    Some(b'G') => {
+                        let (y, x, pic) = (parser.number(), parser.number(), parser.number());
+                        parser.rip_to_eol();
+                        Element::Pic { x, y, pic }
+                    }
+                    Some(b'T') => {
+                        let (y, x) = (parser.number(), parser.number());
+                        let (pic, delay) = (parser.number(), parser.number());
+                        parser.rip_to_eol();
+                        Element::TimedPic { x, y, pic, delay }
+                    }
+                    Some(b'B') => {
+                        let (y, x) = (parser.number(), parser.number());
+                        let (width, height) = (parser.number(), parser.number());
+                        parser.rip_to_eol();
+                        Element::Bar {
+                            x,
+                            y,
+                            width,
+                            height,
+                        }
+                    }
+                    Some(b'L') => {
+                        let (y, x) = (parser.number(), parser.number());
+                        parser.rip_to_eol();
+                        Element::Locate { x, y }
+                    }
+                    // Unknown commands are skipped, like the original does
+                    Some(_) => continue,
+                }
+            }
+            b'\n' => {
+                flush(&mut run, &mut pages);
+                Element::NewLine
+            }
+            b'\t' => {
+                flush(&mut run, &mut pages);
+                Element::Tab
+            }
+            b'\r' => continue,
+            c => {
+                run.push(c as char);
+                continue;
+            }
+        };
+
+        if let Some(page) = pages.last_mut() {
+            page.elements.push(element);
+        }
+    }
+    flush(&mut run, &mut pages);
+
+    pages
+}
+
+/// Cursor and margins of the page being laid out
+struct Layout {
+    px: usize,
+    py: usize,
+    rowon: usize,
+    left_margin: [usize; TEXTROWS],
+    right_margin: [usize; TEXTROWS],
+    done: bool,
+}
+
+impl Layout {
+    fn new_line(&mut self) {
+        self.rowon += 1;
+        if self.rowon >= TEXTROWS {
+            self.done = true;
+            return;
+        }
+        self.px = self.left_margin[self.rowon];
+        self.py += FONTHEIGHT;
+    }
+
+    /// Keeps text out of the rows covered by a pic or bar
+    fn flow_around(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        let mid = x + width / 2;
+        let top = y.saturating_sub(TOPMARGIN) / FONTHEIGHT;
+        let bottom = ((y + height).saturating_sub(TOPMARGIN) / FONTHEIGHT).min(TEXTROWS - 1);
+
+        for row in top..=bottom {
+            if mid > SCREENMID {
+                self.right_margin[row] = x.saturating_sub(PICMARGIN);
+            } else {
+                self.left_margin[row] = x + width + PICMARGIN;
+            }
+        }
+
+        self.px = self.px.max(self.left_margin[self.rowon]);
+    }
+}
+
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| c as u8).collect()
+}
+
+/// Chunk numbers of the pics framing the help and end art pages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowFrame {
+    pub top: usize,
+    pub left: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+impl WindowFrame {
+    /// `H_TOPWINDOWPIC` and the others of Wolfenstein 3D
+    pub const WL6: WindowFrame = WindowFrame {
+        top: GraphicNum::H_TOPWINDOWPIC as usize,
+        left: GraphicNum::H_LEFTWINDOWPIC as usize,
+        right: GraphicNum::H_RIGHTWINDOWPIC as usize,
+        bottom: GraphicNum::H_BOTTOMINFOPIC as usize,
+    };
+}
+
+/// Draws `page` onto a 320x200 surface like the original help viewer, inside
+/// the pics of `frame`. `load_pic` is asked for every pic on the page by chunk
+/// number, pics it has no answer for are left out.
+pub fn draw_page<F: FnMut(usize) -> Option<Pic>>(
+    page: &Page,
+    font: &Font,
+    frame: &WindowFrame,
+    mut load_pic: F,
+    surface: &mut Surface,
+) {
+    surface.fill(BACKCOLOR);
+
+    for (pic, x, y) in [
+        (frame.top, 0, 0),
+        (frame.left, 0, 8),
+        (frame.right, 312, 8),
+        (frame.bottom, 8, 176),
+    ] {
+        if let Some(pic) = load_pic(pic) {
+            pic.draw(surface, x, y);
+        }
+    }
+
+    let mut layout = Layout {
+        px: LEFTMARGIN,
+        py: TOPMARGIN,
+        rowon: 0,
+        left_margin: [LEFTMARGIN; TEXTROWS],
+        right_margin: [SCREENPIXWIDTH - RIGHTMARGIN; TEXTROWS],
+        done: false,
+    };
+    let mut color = 0;
+
+    for element in &page.elements {
+        if layout.done {
+            break;
+        }
+
+        match element {
+            Element::Text(text) => {
+                for (i, word) in text.split(' ').enumerate() {
+                    if i > 0 {
+                        layout.px += SPACEWIDTH;
+                    }
+                    if word.is_empty() {
+                        continue;
+                    }
+
+                    let word = latin1(word);
+                    let (width, _) = font.measure(&word);
+                    if layout.px + width > layout.right_margin[layout.rowon] {
+                        layout.new_line();
+                        if layout.done {
+                            break;
+                        }
+                    }
+                    font.draw(&word, surface, layout.px as i32, layout.py as i32, color);
+                    layout.px += width;
+                }
+            }
+            Element::NewLine => layout.new_line(),
+            Element::Tab => layout.px = (layout.px + 8) & !7,
+            Element::Center => layout.px = SCREENMID,
+            Element::Color(c) => color = *c,
+            &Element::Pic { x, y, pic } => {
+                if let Some(pic) = load_pic(pic) {
+                    pic.draw(surface, (x & !7) as i32, y as i32);
+                    let (width, height) = (pic.size.width as usize, pic.size.height as usize);
+                    layout.flow_around(x, y, width, height);
+                }
+            }
+            &Element::TimedPic { x, y, pic, .. } => {
+                if let Some(pic) = load_pic(pic) {
+                    pic.draw(surface, (x & !7) as i32, y as i32);
+                }
+            }
+            &Element::Bar {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                surface.fill_rect(x, y, width, height, BACKCOLOR);
+                layout.flow_around(x, y, width, height);
+            }
+            &Element::Locate { x, y } => {
+                layout.rowon = (y.saturating_sub(TOPMARGIN) / FONTHEIGHT).min(TEXTROWS - 1);
+                layout.py = TOPMARGIN + layout.rowon * FONTHEIGHT;
+                layout.px = x;
+            }
+        }
+    }
+}
+
+/// Draws "pg 1 of 9" in the bottom right corner of the window frame
+pub fn draw_page_number(font: &Font, page: usize, num_pages: usize, surface: &mut Surface) {
+    let text = format!("pg {} of {}", page + 1, num_pages);
+    font.draw(&text, surface, 213, 183, 0x4f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gr::PicSize;
+
+    #[test]
+    fn test_parse_article() {
+        let text = b"junk before the first page\r\n\
+            ^P\r\n\
+            ^C0AHello\tworld ^; comment\r\n\
+            ^G 16,232,120\r\n\
+            ^L80,24\r\n\
+            ^>Done\r\n\
+            ^P\r\n\
+            ^T 8,8,121,35\r\n\
+            ^B 32,16,64,48\r\n\
+            ^E\r\n\
+            ignored";
+
+        assert_eq!(
+            parse_article(text),
+            [
+                Page {
+                    elements: vec![
+                        Element::Color(0x0A),
+                        Element::Text("Hello".to_string()),
+                        Element::Tab,
+                        Element::Text("world ".to_string()),
+                        Element::Pic {
+                            x: 232,
+                            y: 16,
+                            pic: 120
+                        },
+                        Element::Locate { x: 24, y: 80 },
+                        Element::Center,
+                        Element::Text("Done".to_string()),
+                        Element::NewLine,
+                    ]
+                },
+                Page {
+                    elements: vec![
+                        Element::TimedPic {
+                            x: 8,
+                            y: 8,
+                            pic: 121,
+                            delay: 35
+                        },
+                        Element::Bar {
+                            x: 16,
+                            y: 32,
+                            width: 64,
+                            height: 48
+                        },
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_draw_page_wraps_around_pics() {
+        // Every glyph is a solid 8x10 block
+        let font = Font {
+            height: 10,
+            widths: [8; 256],
+            glyphs: vec![vec![1; 80]; 256],
+        };
+        let pic = || Pic {
+            size: PicSize {
+                width: 16,
+                height: 16,
+            },
+            data: vec![5; 256],
+        };
+
+        let page = Page {
+            elements: vec![
+                Element::Pic {
+                    x: 280,
+                    y: 16,
+                    pic: 100,
+                },
+                // 33 characters don't fit between the left margin and the pic
+                Element::Text("a".repeat(33)),
+            ],
+        };
+
+        let mut surface = Surface::new(320, 200);
+        draw_page(
+            &page,
+            &font,
+            &WindowFrame::WL6,
+            |n| (n == 100).then(pic),
+            &mut surface,
+        );
+        let buffer = &surface.pixels;
+
+        assert_eq!(buffer[0], BACKCOLOR);
+        assert_eq!(buffer[16 * 320 + 280], 5);
+        // Like the original, a word wraps once even if the next row is just as narrow
+        assert_eq!(buffer[16 * 320 + LEFTMARGIN], BACKCOLOR);
+        assert_eq!(buffer[26 * 320 + LEFTMARGIN], 0);
+
+        // The frame comes from the caller, as its pics are numbered differently per game
+        let frame = WindowFrame {
+            top: 100,
+            ..WindowFrame::WL6
+        };
+        draw_page(&page, &font, &frame, |n| (n == 100).then(pic), &mut surface);
+        assert_eq!(surface.pixels[0], 5);
+    }
+}
@@ -1,6 +1,11 @@
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::io::{Read, Seek};
 
+use crate::{
+    datasource::{DataSource, ReadSeek},
+    variant::GameVariant,
+};
+
 pub fn read_audiohed<R: Read + Seek>(reader: &mut R) -> std::io::Result<Vec<u32>> {
     let mut offsets = Vec::new();
 
@@ -27,3 +32,45 @@ pub fn read_audiot_chunk<R: Read + Seek>(
 
     Ok(buffer)
 }
+
+/// The chunk offsets of AUDIOHED together with the AUDIOT they point into
+pub struct AudioArchive<R = Box<dyn ReadSeek>> {
+    offsets: Vec<u32>,
+    reader: R,
+}
+
+impl AudioArchive {
+    pub fn from_source(source: &dyn DataSource, variant: GameVariant) -> std::io::Result<Self> {
+        let extension = variant.audio_extension();
+        let mut audiohed = source.open(&format!("AUDIOHED.{}", extension))?;
+
+        Self::new(
+            &mut audiohed,
+            source.open(&format!("AUDIOT.{}", extension))?,
+        )
+    }
+}
+
+impl<R: Read + Seek> AudioArchive<R> {
+    pub fn new<H: Read + Seek>(audiohed: &mut H, reader: R) -> std::io::Result<Self> {
+        Ok(Self {
+            offsets: read_audiohed(audiohed)?,
+            reader,
+        })
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    pub fn chunk(&mut self, index: usize) -> std::io::Result<Vec<u8>> {
+        if index >= self.num_chunks() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("AUDIOT has no chunk {}", index),
+            ));
+        }
+
+        read_audiot_chunk(&mut self.reader, index, &self.offsets)
+    }
+}
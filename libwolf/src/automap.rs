@@ -0,0 +1,219 @@
+use crate::{
+    gamemaps::Gamemaps,
+    objects::{Difficulty, Dir, ObjectSpawn, StaticKind},
+    surface::Surface,
+    tiles::{DoorKind, DoorOrientation, Tile},
+    vswap::VSWAPArchive,
+};
+
+//
+// GAMEPAL indices used for the flat parts of the map
+//
+pub const FLOOR_COLOR: u8 = 25;
+pub const WALL_COLOR: u8 = 20;
+pub const UNKNOWN_COLOR: u8 = 183;
+pub const DOOR_COLOR: u8 = 119;
+pub const GOLD_DOOR_COLOR: u8 = 71;
+pub const SILVER_DOOR_COLOR: u8 = 16;
+pub const ELEVATOR_DOOR_COLOR: u8 = 3;
+pub const PUSHWALL_COLOR: u8 = 13;
+pub const PLAYER_COLOR: u8 = 95;
+pub const ENEMY_COLOR: u8 = 32;
+pub const TREASURE_COLOR: u8 = 14;
+pub const HEALTH_COLOR: u8 = 143;
+pub const AMMO_COLOR: u8 = 58;
+pub const KEY_COLOR: u8 = 68;
+pub const DECORATION_COLOR: u8 = 29;
+pub const EXIT_COLOR: u8 = 10;
+
+const TEXTURE_SIZE: usize = 64;
+
+/// Renders a top-down view of the map with `cell_size` pixels per tile, 0 being taken as 1
+///
+/// Walls are drawn with their textures when `vswap` is given, or a flat colour otherwise.
+/// Doors, pushwalls and the objects present on `difficulty` are drawn on top.
+pub fn render_map(
+    map: &Gamemaps,
+    vswap: Option<&VSWAPArchive>,
+    cell_size: usize,
+    difficulty: Difficulty,
+) -> Surface {
+    let cell_size = cell_size.max(1);
+    let mut image = Surface::new(
+        map.width as usize * cell_size,
+        map.height as usize * cell_size,
+    );
+
+    for y in 0..map.height as usize {
+        for x in 0..map.width as usize {
+            let (left, top) = (x * cell_size, y * cell_size);
+
+            match map.tile(x, y) {
+                Some(Tile::Wall(wall)) => {
+                    // Light side texture of the wall
+                    let texture = vswap.and_then(|vswap| {
+                        vswap
+                            .wall_chunks
+                            .get((wall as usize - 1) * 2)
+                            .filter(|data| data.len() >= TEXTURE_SIZE * TEXTURE_SIZE)
+                    });
+                    match texture {
+                        Some(texture) => draw_texture(&mut image, left, top, cell_size, texture),
+                        None => image.fill_rect(left, top, cell_size, cell_size, WALL_COLOR),
+                    }
+                }
+                Some(Tile::Door { kind, orientation }) => {
+                    image.fill_rect(left, top, cell_size, cell_size, FLOOR_COLOR);
+
+                    let color = match kind {
+                        DoorKind::GoldKey => GOLD_DOOR_COLOR,
+                        DoorKind::SilverKey => SILVER_DOOR_COLOR,
+                        DoorKind::Elevator => ELEVATOR_DOOR_COLOR,
+                        _ => DOOR_COLOR,
+                    };
+
+                    // The door slab runs across the way it is walked through
+                    let thickness = (cell_size / 4).max(1);
+                    let middle = (cell_size - thickness) / 2;
+                    match orientation {
+                        DoorOrientation::Vertical => {
+                            image.fill_rect(left + middle, top, thickness, cell_size, color)
+                        }
+                        DoorOrientation::Horizontal => {
+                            image.fill_rect(left, top + middle, cell_size, thickness, color)
+                        }
+                    }
+                }
+                Some(Tile::Floor { .. } | Tile::AmbushFloor) => {
+                    image.fill_rect(left, top, cell_size, cell_size, FLOOR_COLOR)
+                }
+                Some(Tile::Unknown(_)) | None => {
+                    image.fill_rect(left, top, cell_size, cell_size, UNKNOWN_COLOR)
+                }
+            }
+        }
+    }
+
+    for spawn in map.spawns(difficulty) {
+        let (left, top) = (spawn.x * cell_size, spawn.y * cell_size);
+
+        match spawn.object {
+            ObjectSpawn::PlayerStart(dir) => {
+                draw_square(
+                    &mut image,
+                    left,
+                    top,
+                    cell_size,
+                    cell_size / 2,
+                    PLAYER_COLOR,
+                );
+                draw_pointer(&mut image, left, top, cell_size, dir, PLAYER_COLOR);
+            }
+            ObjectSpawn::Enemy { direction, .. } => {
+                draw_square(&mut image, left, top, cell_size, cell_size / 2, ENEMY_COLOR);
+                if let Some(dir) = direction {
+                    draw_pointer(&mut image, left, top, cell_size, dir, ENEMY_COLOR);
+                }
+            }
+            ObjectSpawn::Static(kind) => {
+                let color = if matches!(kind, StaticKind::GoldKey | StaticKind::SilverKey) {
+                    Some(KEY_COLOR)
+                } else if kind.treasure_points().is_some() {
+                    Some(TREASURE_COLOR)
+                } else if kind.health() > 0 {
+                    Some(HEALTH_COLOR)
+                } else if kind.ammo() > 0 {
+                    Some(AMMO_COLOR)
+                } else if kind.is_blocking() {
+                    Some(DECORATION_COLOR)
+                } else {
+                    None
+                };
+
+                if let Some(color) = color {
+                    draw_square(&mut image, left, top, cell_size, cell_size / 3, color);
+                }
+            }
+            ObjectSpawn::Pushwall => draw_frame(&mut image, left, top, cell_size, PUSHWALL_COLOR),
+            ObjectSpawn::ExitTile => draw_frame(&mut image, left, top, cell_size, EXIT_COLOR),
+            _ => {}
+        }
+    }
+
+    image
+}
+
+fn draw_texture(image: &mut Surface, left: usize, top: usize, size: usize, texture: &[u8]) {
+    // Wall textures are stored column by column
+    for y in 0..size {
+        for x in 0..size {
+            let color = texture[(x * TEXTURE_SIZE / size) * TEXTURE_SIZE + y * TEXTURE_SIZE / size];
+            image.set_pixel(left + x, top + y, color);
+        }
+    }
+}
+
+fn draw_square(image: &mut Surface, left: usize, top: usize, cell: usize, size: usize, color: u8) {
+    let size = size.max(1);
+    let offset = (cell - size.min(cell)) / 2;
+    image.fill_rect(left + offset, top + offset, size, size, color);
+}
+
+fn draw_frame(image: &mut Surface, left: usize, top: usize, size: usize, color: u8) {
+    image.fill_rect(left, top, size, 1, color);
+    image.fill_rect(left, top + size - 1, size, 1, color);
+    image.fill_rect(left, top, 1, size, color);
+    image.fill_rect(left + size - 1, top, 1, size, color);
+}
+
+/// Line from the middle of the cell to its edge in `dir`
+fn draw_pointer(image: &mut Surface, left: usize, top: usize, size: usize, dir: Dir, color: u8) {
+    let (dx, dy) = dir.delta();
+    let middle = (size / 2) as i32;
+
+    for step in 0..=middle {
+        let x = left as i32 + (middle + dx * step).min(size as i32 - 1);
+        let y = top as i32 + (middle + dy * step).min(size as i32 - 1);
+        image.set_pixel(x as usize, y as usize, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_map() {
+        let words = |codes: &[u16]| {
+            codes
+                .iter()
+                .flat_map(|code| code.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+        let map = Gamemaps {
+            index: 0,
+            plane0: words(&[1, 107, 90, 107]),
+            plane1: words(&[98, 19, 0, 108]),
+            plane2: words(&[0; 4]),
+            width: 4,
+            height: 1,
+            name: "Test".to_string(),
+        };
+
+        let image = render_map(&map, None, 8, Difficulty::Hard);
+        assert_eq!((image.width, image.height), (32, 8));
+
+        let pixel = |x: usize, y: usize| image.pixels[y * image.width + x];
+        assert_eq!(pixel(0, 0), PUSHWALL_COLOR);
+        assert_eq!(pixel(3, 3), WALL_COLOR);
+        assert_eq!(pixel(8, 0), FLOOR_COLOR);
+        assert_eq!(pixel(12, 4), PLAYER_COLOR);
+        assert_eq!(pixel(12, 0), PLAYER_COLOR);
+        assert_eq!(pixel(19, 0), DOOR_COLOR);
+        assert_eq!(pixel(16, 0), FLOOR_COLOR);
+        assert_eq!(pixel(28, 4), ENEMY_COLOR);
+
+        let image = render_map(&map, None, 0, Difficulty::Hard);
+        assert_eq!((image.width, image.height), (4, 1));
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+/// How often [`ChunkCache::get`] found its chunk
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Entry {
+    data: Vec<u8>,
+    last_used: u64,
+}
+
+/// Expanded chunks by chunk index, dropping the least recently used chunk
+/// once more than `capacity` are held. A capacity of 0 caches nothing.
+#[derive(Default)]
+pub struct ChunkCache {
+    capacity: usize,
+    entries: HashMap<usize, Entry>,
+    /// Incremented on every access, to order the entries by their last use
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl ChunkCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, chunk_index: usize) -> bool {
+        self.entries.contains_key(&chunk_index)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Looks up a chunk, counting the outcome in the stats
+    pub fn get(&mut self, chunk_index: usize) -> Option<&[u8]> {
+        self.clock += 1;
+        match self.entries.get_mut(&chunk_index) {
+            Some(entry) => {
+                self.stats.hits += 1;
+                entry.last_used = self.clock;
+                Some(&entry.data)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, chunk_index: usize, data: Vec<u8>) {
+        self.clock += 1;
+        self.entries.insert(
+            chunk_index,
+            Entry {
+                data,
+                last_used: self.clock,
+            },
+        );
+        self.evict();
+    }
+
+    /// Drops every chunk, the stats are kept
+    pub fn purge(&mut self) {
+        self.entries.clear();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(&chunk_index, _)| chunk_index)
+                .unwrap();
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_is_dropped() {
+        let mut cache = ChunkCache::new(2);
+        cache.insert(1, vec![1]);
+        cache.insert(2, vec![2]);
+        assert_eq!(cache.get(1), Some(&[1][..]));
+
+        cache.insert(3, vec![3]);
+        assert!(cache.contains(1));
+        assert!(!cache.contains(2));
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+        cache.set_capacity(0);
+        assert!(cache.is_empty());
+        cache.insert(4, vec![4]);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use zip::ZipArchive;
+
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Somewhere data files can be opened by their logical name, e.g. `VSWAP.WL6`.
+/// Names are matched regardless of case by every source.
+pub trait DataSource {
+    fn open(&self, name: &str) -> std::io::Result<Box<dyn ReadSeek>>;
+
+    fn exists(&self, name: &str) -> bool;
+
+    fn read(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.open(name)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+fn not_found(name: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("{} does not exist", name),
+    )
+}
+
+/// Files in a directory on disk
+pub struct DirSource {
+    path: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<PathBuf> {
+        std::fs::read_dir(&self.path)
+            .ok()?
+            .filter_map(Result::ok)
+            .find(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(name)
+            })
+            .map(|entry| entry.path())
+    }
+}
+
+impl DataSource for DirSource {
+    fn open(&self, name: &str) -> std::io::Result<Box<dyn ReadSeek>> {
+        let path = self.find(name).ok_or_else(|| not_found(name))?;
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.find(name).is_some()
+    }
+}
+
+/// Files held in memory, handy for synthetic data and tests
+#[derive(Default)]
+pub struct MemorySource {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) {
+        self.files.insert(name.to_uppercase(), data);
+    }
+}
+
+impl From<HashMap<String, Vec<u8>>> for MemorySource {
+    fn from(files: HashMap<String, Vec<u8>>) -> Self {
+        Self {
+            files: files
+                .into_iter()
+                .map(|(name, data)| (name.to_uppercase(), data))
+                .collect(),
+        }
+    }
+}
+
+impl DataSource for MemorySource {
+    fn open(&self, name: &str) -> std::io::Result<Box<dyn ReadSeek>> {
+        self.read(name)
+            .map(|data| Box::new(Cursor::new(data)) as Box<dyn ReadSeek>)
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.files.contains_key(&name.to_uppercase())
+    }
+
+    fn read(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        self.files
+            .get(&name.to_uppercase())
+            .cloned()
+            .ok_or_else(|| not_found(name))
+    }
+}
+
+/// Files in a zip archive. Entries are looked up by their file name alone, so
+/// data kept in a folder inside the archive is found as well.
+pub struct ZipSource<R> {
+    archive: Mutex<ZipArchive<R>>,
+    /// Upper case file name to the index of its entry
+    entries: HashMap<String, usize>,
+}
+
+impl<R: Read + Seek> ZipSource<R> {
+    pub fn new(reader: R) -> zip::result::ZipResult<Self> {
+        let archive = ZipArchive::new(reader)?;
+        let entries = archive
+            .file_names()
+            .enumerate()
+            .filter(|(_, name)| !name.ends_with('/'))
+            .map(|(index, name)| {
+                let name = name.rsplit('/').next().unwrap_or(name).to_uppercase();
+                (name, index)
+            })
+            .collect();
+
+        Ok(Self {
+            archive: Mutex::new(archive),
+            entries,
+        })
+    }
+}
+
+impl ZipSource<BufReader<File>> {
+    pub fn open_file(path: &str) -> zip::result::ZipResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> DataSource for ZipSource<R> {
+    fn open(&self, name: &str) -> std::io::Result<Box<dyn ReadSeek>> {
+        self.read(name)
+            .map(|data| Box::new(Cursor::new(data)) as Box<dyn ReadSeek>)
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.entries.contains_key(&name.to_uppercase())
+    }
+
+    fn read(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        let index = *self
+            .entries
+            .get(&name.to_uppercase())
+            .ok_or_else(|| not_found(name))?;
+
+        // Entries can't be seeked into, so they are always read whole
+        let mut archive = self.archive.lock().unwrap();
+        let mut entry = archive.by_index(index)?;
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Several sources stacked on top of each other, e.g. a mod directory over
+/// the original game. Later layers override files of earlier ones.
+pub struct OverlaySource {
+    layers: Vec<Box<dyn DataSource>>,
+}
+
+impl OverlaySource {
+    pub fn new(layers: Vec<Box<dyn DataSource>>) -> Self {
+        Self { layers }
+    }
+
+    fn layer(&self, name: &str) -> Option<&dyn DataSource> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|layer| layer.exists(name))
+            .map(|layer| layer.as_ref())
+    }
+}
+
+impl DataSource for OverlaySource {
+    fn open(&self, name: &str) -> std::io::Result<Box<dyn ReadSeek>> {
+        self.layer(name).ok_or_else(|| not_found(name))?.open(name)
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.layer(name).is_some()
+    }
+
+    fn read(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        self.layer(name).ok_or_else(|| not_found(name))?.read(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn test_overlay_and_zip() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        // Directory entries are skipped, the files after them must keep their index
+        zip.add_directory("wolf3d/", options).unwrap();
+        zip.start_file("wolf3d/vswap.wl6", options).unwrap();
+        zip.write_all(b"base vswap").unwrap();
+        zip.start_file("wolf3d/AUDIOT.WL6", options).unwrap();
+        zip.write_all(b"base audiot").unwrap();
+        let base = ZipSource::new(zip.finish().unwrap()).unwrap();
+
+        let mut mod_files = MemorySource::new();
+        mod_files.insert("Vswap.Wl6", b"mod vswap".to_vec());
+
+        let overlay = OverlaySource::new(vec![Box::new(base), Box::new(mod_files)]);
+        assert_eq!(overlay.read("VSWAP.WL6").unwrap(), b"mod vswap");
+        assert_eq!(overlay.read("AUDIOT.WL6").unwrap(), b"base audiot");
+        assert!(!overlay.exists("GAMEMAPS.WL6"));
+        assert_eq!(
+            overlay.open("GAMEMAPS.WL6").err().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+}
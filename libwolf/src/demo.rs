@@ -0,0 +1,137 @@
+use byteorder::{ByteOrder, LittleEndian};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DemoError {
+    #[error("The demo header is truncated")]
+    TruncatedHeader,
+    #[error("The demo claims to be {length} bytes long but the chunk holds {size}")]
+    InvalidLength { length: usize, size: usize },
+    #[error("{0} tics don't fit in a demo")]
+    TooLong(usize),
+}
+
+/// Game tics covered by each recorded tic, the movement is scaled by it on playback
+pub const DEMOTICS: u8 = 4;
+
+/// Map number, a word holding the length of the whole demo and an unused byte
+const HEADER_SIZE: usize = 4;
+
+/// The input of one recorded tic
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DemoTic {
+    /// One bit per button, see the `BT_*` constants
+    pub buttons: u8,
+    pub dx: i8,
+    pub dy: i8,
+}
+
+impl DemoTic {
+    pub const BT_ATTACK: u8 = 1 << 0;
+    pub const BT_STRAFE: u8 = 1 << 1;
+    pub const BT_RUN: u8 = 1 << 2;
+    pub const BT_USE: u8 = 1 << 3;
+    pub const BT_READYKNIFE: u8 = 1 << 4;
+    pub const BT_READYPISTOL: u8 = 1 << 5;
+    pub const BT_READYMACHINEGUN: u8 = 1 << 6;
+    pub const BT_READYCHAINGUN: u8 = 1 << 7;
+}
+
+/// A recorded demo, played back on the hard difficulty of the first episode
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Demo {
+    pub map: u8,
+    pub tics: Vec<DemoTic>,
+}
+
+impl Demo {
+    pub fn from_chunk(data: &[u8]) -> Result<Self, DemoError> {
+        if data.len() < HEADER_SIZE {
+            return Err(DemoError::TruncatedHeader);
+        }
+
+        let length = LittleEndian::read_u16(&data[1..3]) as usize;
+        if length < HEADER_SIZE || length > data.len() || !(length - HEADER_SIZE).is_multiple_of(3)
+        {
+            return Err(DemoError::InvalidLength {
+                length,
+                size: data.len(),
+            });
+        }
+
+        let tics = data[HEADER_SIZE..length]
+            .chunks_exact(3)
+            .map(|tic| DemoTic {
+                buttons: tic[0],
+                dx: tic[1] as i8,
+                dy: tic[2] as i8,
+            })
+            .collect();
+
+        Ok(Demo { map: data[0], tics })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = DemoTic> + '_ {
+        self.tics.iter().copied()
+    }
+
+    /// Serializes the demo into the layout of a `T_DEMO*` chunk
+    pub fn to_chunk(&self) -> Result<Vec<u8>, DemoError> {
+        let length = HEADER_SIZE + self.tics.len() * 3;
+        if length > u16::MAX as usize {
+            return Err(DemoError::TooLong(self.tics.len()));
+        }
+
+        let mut data = Vec::with_capacity(length);
+        data.push(self.map);
+        data.extend_from_slice(&(length as u16).to_le_bytes());
+        data.push(0);
+        for tic in &self.tics {
+            data.extend_from_slice(&[tic.buttons, tic.dx as u8, tic.dy as u8]);
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_round_trip() {
+        let demo = Demo {
+            map: 2,
+            tics: vec![
+                DemoTic {
+                    buttons: DemoTic::BT_ATTACK | DemoTic::BT_RUN,
+                    dx: -100,
+                    dy: 0,
+                },
+                DemoTic {
+                    buttons: 0,
+                    dx: 0,
+                    dy: 127,
+                },
+            ],
+        };
+
+        let mut chunk = demo.to_chunk().unwrap();
+        assert_eq!(chunk[..4], [2, 10, 0, 0]);
+        assert_eq!(chunk[4..7], [0b101, 156, 0]);
+
+        // Anything past the length in the header is ignored
+        chunk.extend_from_slice(&[0; 6]);
+        let loaded = Demo::from_chunk(&chunk).unwrap();
+        assert_eq!(loaded, demo);
+        assert_eq!(loaded.iter().nth(1).unwrap().dy, 127);
+
+        assert_eq!(
+            Demo::from_chunk(&chunk[..8]),
+            Err(DemoError::InvalidLength {
+                length: 10,
+                size: 8
+            })
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use byteorder::{ByteOrder, LittleEndian};
+use thiserror::Error;
+
+use crate::surface::Surface;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FontError {
+    #[error("The font header is truncated")]
+    TruncatedHeader,
+    #[error("Glyph {0} lies outside of the font chunk")]
+    GlyphOutOfRange(u8),
+}
+
+/// A proportional font, decoded from the `fontstruct` of a font chunk
+pub struct Font {
+    pub height: u16,
+    pub widths: [u8; 256],
+    /// `width * height` bytes per character, row by row. Pixels that are not
+    /// zero are drawn in the text colour.
+    pub glyphs: Vec<Vec<u8>>,
+}
+
+/// `height`, then 256 glyph offsets and 256 widths
+const HEADER_SIZE: usize = 2 + 256 * 2 + 256;
+
+impl Font {
+    pub fn from_chunk(data: &[u8]) -> Result<Self, FontError> {
+        if data.len() < HEADER_SIZE {
+            return Err(FontError::TruncatedHeader);
+        }
+
+        let height = LittleEndian::read_u16(&data[0..2]);
+        let mut widths = [0; 256];
+        widths.copy_from_slice(&data[2 + 256 * 2..HEADER_SIZE]);
+
+        let glyphs = (0..256)
+            .map(|c| {
+                let location = LittleEndian::read_u16(&data[2 + c * 2..]) as usize;
+                let size = widths[c] as usize * height as usize;
+                data.get(location..location + size)
+                    .map(<[u8]>::to_vec)
+                    .ok_or(FontError::GlyphOutOfRange(c as u8))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Font {
+            height,
+            widths,
+            glyphs,
+        })
+    }
+
+    /// Width and height of `text` in pixels. Characters are taken byte by
+    /// byte, as the fonts only cover the original code page.
+    pub fn measure<T: AsRef<[u8]>>(&self, text: T) -> (usize, usize) {
+        let width = text
+            .as_ref()
+            .iter()
+            .map(|&c| self.widths[c as usize] as usize)
+            .sum();
+        (width, self.height as usize)
+    }
+
+    /// Draws `text` with its top left corner at `(x, y)`, clipping whatever
+    /// falls outside of the surface. Returns the width drawn.
+    pub fn draw<T: AsRef<[u8]>>(
+        &self,
+        text: T,
+        surface: &mut Surface,
+        x: i32,
+        y: i32,
+        color: u8,
+    ) -> usize {
+        let mut pen_x = x;
+
+        for &c in text.as_ref() {
+            let width = self.widths[c as usize] as usize;
+            let glyph = &self.glyphs[c as usize];
+
+            for (row, pixels) in glyph.chunks_exact(width.max(1)).enumerate() {
+                for (column, &pixel) in pixels.iter().enumerate() {
+                    let (dst_x, dst_y) = (pen_x + column as i32, y + row as i32);
+                    if pixel != 0 && dst_x >= 0 && dst_y >= 0 {
+                        surface.set_pixel(dst_x as usize, dst_y as usize, color);
+                    }
+                }
+            }
+
+            pen_x += width as i32;
+        }
+
+        (pen_x - x) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A font of height 2 where `A` is 2 pixels wide and space is 1 pixel wide
+    fn test_font_chunk() -> Vec<u8> {
+        let mut data = vec![0; HEADER_SIZE];
+        data[0] = 2;
+        let glyph_a = HEADER_SIZE as u16;
+        let glyph_space = glyph_a + 4;
+        data[2 + b'A' as usize * 2..][..2].copy_from_slice(&glyph_a.to_le_bytes());
+        data[2 + b' ' as usize * 2..][..2].copy_from_slice(&glyph_space.to_le_bytes());
+        data[2 + 512 + b'A' as usize] = 2;
+        data[2 + 512 + b' ' as usize] = 1;
+        data.extend_from_slice(&[1, 0, 1, 1, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_measure_and_draw() {
+        let font = Font::from_chunk(&test_font_chunk()).unwrap();
+        assert_eq!(font.measure("A A"), (5, 2));
+
+        let mut surface = Surface::new(320, 200);
+        assert_eq!(font.draw("A A", &mut surface, 318, 10, 15), 5);
+        let buffer = surface.pixels;
+        assert_eq!(&buffer[10 * 320 + 318..10 * 320 + 320], [15, 0]);
+        assert_eq!(&buffer[11 * 320 + 318..11 * 320 + 320], [15, 15]);
+        // The second A is past the right edge and must not wrap to the next row
+        assert_eq!(&buffer[11 * 320..11 * 320 + 2], [0, 0]);
+
+        let mut truncated = test_font_chunk();
+        truncated.truncate(HEADER_SIZE + 2);
+        assert!(matches!(
+            Font::from_chunk(&truncated),
+            Err(FontError::GlyphOutOfRange(b' '))
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+use thiserror::Error;
+
+use crate::{
+    audiot::AudioArchive,
+    datasource::{DataSource, DirSource},
+    gamemaps::{GamemapsError, MapArchive},
+    gr::{GrArchive, GrArchiveError},
+    grlayout::GrLayout,
+    identify::{self, Identification, IdentifyError},
+    imf::Imf,
+    variant::GameVariant,
+    vswap::VSWAPArchive,
+};
+
+#[derive(Debug, Error)]
+pub enum GameDataError {
+    #[error("Could not read {name}: {source}")]
+    Io {
+        name: String,
+        source: std::io::Error,
+    },
+    #[error("No Wolf3D or Spear of Destiny data found")]
+    NoGameData,
+    #[error("{0} is missing")]
+    MissingFile(String),
+    #[error(transparent)]
+    Gamemaps(#[from] GamemapsError),
+    #[error(transparent)]
+    Graphics(#[from] GrArchiveError),
+}
+
+impl GameDataError {
+    fn from_io(name: String, source: std::io::Error) -> Self {
+        if source.kind() == std::io::ErrorKind::NotFound {
+            GameDataError::MissingFile(name)
+        } else {
+            GameDataError::Io { name, source }
+        }
+    }
+}
+
+/// All data files of one installation. File names are matched regardless of
+/// case, and each archive is only opened the first time it is asked for.
+pub struct GameData {
+    variant: GameVariant,
+    source: Box<dyn DataSource>,
+    gr_layout: GrLayout,
+    maps: Option<MapArchive>,
+    graphics: Option<GrArchive>,
+    pages: Option<VSWAPArchive>,
+    audio: Option<AudioArchive>,
+}
+
+impl GameData {
+    pub fn open(path: &str) -> Result<Self, GameDataError> {
+        Self::from_source(Box::new(DirSource::new(path)))
+    }
+
+    pub fn from_source(source: Box<dyn DataSource>) -> Result<Self, GameDataError> {
+        let variant = GameVariant::detect_with(|name| source.exists(name))
+            .ok_or(GameDataError::NoGameData)?;
+
+        Ok(Self {
+            variant,
+            source,
+            gr_layout: variant.gr_layout(),
+            maps: None,
+            graphics: None,
+            pages: None,
+            audio: None,
+        })
+    }
+
+    pub fn variant(&self) -> GameVariant {
+        self.variant
+    }
+
+    pub fn source(&self) -> &dyn DataSource {
+        self.source.as_ref()
+    }
+
+    /// Replaces the built-in graphics layout of the variant, for games with
+    /// extra or fewer chunks. Graphics are reopened with it on next access.
+    pub fn set_gr_layout(&mut self, layout: GrLayout) {
+        self.gr_layout = layout;
+        self.graphics = None;
+    }
+
+    /// Checksums the data files to tell which release they come from
+    pub fn identify(&self) -> Result<Identification, IdentifyError> {
+        identify::identify(self.source.as_ref())
+    }
+
+    /// Data files of the detected variant that are not present
+    pub fn missing_files(&self) -> Vec<String> {
+        self.variant
+            .data_files()
+            .into_iter()
+            .filter(|name| !self.source.exists(name))
+            .collect()
+    }
+
+    /// The first missing file among `names`, to report before an archive
+    /// fails on whichever file it happens to open first
+    fn require(&self, names: &[String]) -> Result<(), GameDataError> {
+        match names.iter().find(|name| !self.source.exists(name)) {
+            Some(name) => Err(GameDataError::MissingFile(name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    pub fn maps(&mut self) -> Result<&mut MapArchive, GameDataError> {
+        if self.maps.is_none() {
+            self.maps = Some(MapArchive::from_source(self.source.as_ref(), self.variant)?);
+        }
+
+        Ok(self.maps.as_mut().unwrap())
+    }
+
+    pub fn graphics(&mut self) -> Result<&GrArchive, GameDataError> {
+        if self.graphics.is_none() {
+            let extension = self.variant.graphics_extension();
+            self.require(
+                &["VGADICT", "VGAHEAD", "VGAGRAPH"].map(|name| format!("{}.{}", name, extension)),
+            )?;
+
+            self.graphics = Some(
+                GrArchive::with_layout(self.source.as_ref(), self.variant, self.gr_layout.clone())
+                    .map_err(|error| match error {
+                        GrArchiveError::Io(source) => {
+                            GameDataError::from_io(format!("VGAGRAPH.{}", extension), source)
+                        }
+                        error => GameDataError::Graphics(error),
+                    })?,
+            );
+        }
+
+        Ok(self.graphics.as_ref().unwrap())
+    }
+
+    pub fn pages(&mut self) -> Result<&VSWAPArchive, GameDataError> {
+        if self.pages.is_none() {
+            self.pages = Some(
+                VSWAPArchive::from_source(self.source.as_ref(), self.variant).map_err(
+                    |source| GameDataError::from_io(self.variant.file_name("VSWAP"), source),
+                )?,
+            );
+        }
+
+        Ok(self.pages.as_ref().unwrap())
+    }
+
+    pub fn audio(&mut self) -> Result<&mut AudioArchive, GameDataError> {
+        if self.audio.is_none() {
+            let extension = self.variant.audio_extension();
+            self.require(&["AUDIOHED", "AUDIOT"].map(|name| format!("{}.{}", name, extension)))?;
+
+            self.audio = Some(
+                AudioArchive::from_source(self.source.as_ref(), self.variant).map_err(
+                    |source| GameDataError::from_io(format!("AUDIOT.{}", extension), source),
+                )?,
+            );
+        }
+
+        Ok(self.audio.as_mut().unwrap())
+    }
+
+    /// Song `music_number` of the variant, ready to be played at `output_sample_rate`
+    pub fn music(
+        &mut self,
+        music_number: usize,
+        output_sample_rate: u32,
+    ) -> Result<Imf, GameDataError> {
+        let name = format!("AUDIOT.{}", self.variant.audio_extension());
+        let chunk = self.variant.music_start() + music_number;
+
+        self.audio()?
+            .chunk(chunk)
+            .and_then(|data| Imf::from_chunk(data, output_sample_rate))
+            .map_err(|source| GameDataError::Io { name, source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamemaps::Gamemaps;
+
+    #[test]
+    fn test_open_ignores_case() {
+        let dir = std::env::temp_dir().join(format!("libwolf-gamedata-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let map = Gamemaps {
+            index: 0,
+            plane0: vec![0; 8],
+            plane1: vec![0; 8],
+            plane2: vec![0; 8],
+            width: 2,
+            height: 2,
+            name: "Tiny".to_string(),
+        };
+        let mut maphead = Vec::new();
+        let mut gamemaps = Vec::new();
+        Gamemaps::write(&[map], &mut maphead, &mut gamemaps).unwrap();
+        std::fs::write(dir.join("maphead.wl6"), maphead).unwrap();
+        std::fs::write(dir.join("GameMaps.Wl6"), gamemaps).unwrap();
+        std::fs::write(dir.join("vswap.wl6"), []).unwrap();
+
+        let mut data = GameData::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(data.variant(), GameVariant::Wolf3d);
+        assert_eq!(
+            data.missing_files(),
+            [
+                "VGADICT.WL6",
+                "VGAHEAD.WL6",
+                "VGAGRAPH.WL6",
+                "AUDIOHED.WL6",
+                "AUDIOT.WL6"
+            ]
+        );
+        assert_eq!(data.maps().unwrap().load_map(0).unwrap().name, "Tiny");
+        assert!(matches!(
+            data.graphics(),
+            Err(GameDataError::MissingFile(name)) if name == "VGADICT.WL6"
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,60 @@
 use std::{
-    fs::File,
-    io::{BufReader, Read},
+    collections::HashMap,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
 };
 
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
 
+use crate::{
+    datasource::{DataSource, DirSource, ReadSeek},
+    tiles::Tile,
+    variant::GameVariant,
+};
+
+#[derive(Debug, Error)]
+pub enum GamemapsError {
+    #[error("Could not open {path}: {source}")]
+    MissingFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("MAPHEAD is truncated")]
+    TruncatedMapHead,
+    #[error("Header of map {map} is truncated")]
+    TruncatedMapHeader { map: usize },
+    #[error("There is no map in slot {map}")]
+    NoSuchMap { map: usize },
+    #[error("Plane {plane} of map {map} lies outside of GAMEMAPS")]
+    PlaneOutOfRange { map: usize, plane: usize },
+    #[error("Carmack expansion of map {map}, plane {plane} failed: {source}")]
+    Carmack {
+        map: usize,
+        plane: usize,
+        source: ExpandError,
+    },
+    #[error("RLEW expansion of map {map}, plane {plane} failed: {source}")]
+    Rlew {
+        map: usize,
+        plane: usize,
+        source: ExpandError,
+    },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ExpandError {
+    #[error("compressed data ended before the expected size was reached")]
+    Underrun,
+    #[error("expanded data exceeds the expected size")]
+    Overrun,
+    #[error("copy refers to data that has not been expanded yet")]
+    BadReference,
+}
+
+#[derive(Clone)]
 pub struct Gamemaps {
+    /// Slot of the map in MAPHEAD, empty slots are skipped when loading
+    pub index: usize,
     pub plane0: Vec<u8>,
     pub plane1: Vec<u8>,
     pub plane2: Vec<u8>,
@@ -14,103 +63,367 @@ pub struct Gamemaps {
     pub name: String,
 }
 
+/// The RLEW tag written by [`Gamemaps::write`], same as the original MAPHEAD.WL6
+pub const RLEW_TAG: u16 = 0xABCD;
+
+const MAP_HEADER_SIZE: usize = 38;
+const MAX_MAPS: usize = 100;
+
 impl Gamemaps {
-    pub fn new(path: &str) -> Vec<Self> {
-        let mut maphead = BufReader::new(File::open(format!("{}/MAPHEAD.WL6", path)).unwrap());
-        let mut gamemaps = vec![];
-        let _ = File::open(format!("{}/GAMEMAPS.WL6", path))
-            .unwrap()
-            .read_to_end(&mut gamemaps);
+    pub fn new(path: &str, variant: GameVariant) -> Result<Vec<Self>, GamemapsError> {
+        MapArchive::open(path, variant)?.load_all()
+    }
+
+    pub fn from_source(
+        source: &dyn DataSource,
+        variant: GameVariant,
+    ) -> Result<Vec<Self>, GamemapsError> {
+        MapArchive::from_source(source, variant)?.load_all()
+    }
 
-        let magic = maphead.read_u16::<LittleEndian>().unwrap();
+    pub fn from_data(maphead: &[u8], gamemaps: &[u8]) -> Result<Vec<Self>, GamemapsError> {
+        MapArchive::from_reader(maphead, Cursor::new(gamemaps))?.load_all()
+    }
 
-        let mut result = vec![];
+    /// Raw little-endian word at `(x, y)` of `plane` 0, 1 or 2
+    pub fn word(&self, plane: usize, x: usize, y: usize) -> Option<u16> {
+        let data = match plane {
+            0 => &self.plane0,
+            1 => &self.plane1,
+            2 => &self.plane2,
+            _ => return None,
+        };
+        if x >= self.width as usize || y >= self.height as usize {
+            return None;
+        }
+
+        let offset = (y * self.width as usize + x) * 2;
+        data.get(offset..offset + 2)
+            .map(|word| u16::from_le_bytes([word[0], word[1]]))
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Option<Tile> {
+        self.word(0, x, y).map(Tile::from_code)
+    }
+
+    /// Writes `maps` into their slots of MAPHEAD and GAMEMAPS of `variant` in `path`
+    pub fn save(maps: &[Self], path: &str, variant: GameVariant) -> std::io::Result<()> {
+        let mut maphead = Vec::new();
+        let mut gamemaps = Vec::new();
+        Self::write(maps, &mut maphead, &mut gamemaps)?;
+
+        std::fs::write(
+            format!("{}/{}", path, variant.file_name("MAPHEAD")),
+            maphead,
+        )?;
+        std::fs::write(
+            format!("{}/{}", path, variant.file_name("GAMEMAPS")),
+            gamemaps,
+        )
+    }
+
+    pub fn write<H: Write, G: Write>(
+        maps: &[Self],
+        maphead: &mut H,
+        gamemaps: &mut G,
+    ) -> std::io::Result<()> {
+        // Offsets are absolute, so the whole file is built in memory first
+        let mut data = b"TED5v1.0".to_vec();
+        let mut pointers = [0i32; MAX_MAPS];
+
+        for map in maps {
+            match pointers.get(map.index) {
+                Some(0) => {}
+                Some(_) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Slot {} is used by more than one map", map.index),
+                    ))
+                }
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Slot {} does not fit in MAPHEAD", map.index),
+                    ))
+                }
+            }
 
-        for _ in 0..100 {
-            let ptr = maphead.read_i32::<LittleEndian>().unwrap();
-            if ptr == 0 {
+            let mut offsets = [0i32; 3];
+            let mut lengths = [0u16; 3];
+
+            for (plane, (offset, length)) in [&map.plane0, &map.plane1, &map.plane2]
+                .into_iter()
+                .zip(offsets.iter_mut().zip(lengths.iter_mut()))
+            {
+                let too_large = || {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("A plane of map {} is too large", map.name),
+                    )
+                };
+                let compressed = rlew_compress(plane, RLEW_TAG)
+                    .and_then(|rlew| carmack_compress(&rlew))
+                    .map_err(|_| too_large())?;
+                *offset = data.len() as i32;
+                *length = u16::try_from(compressed.len()).map_err(|_| too_large())?;
+                data.extend_from_slice(&compressed);
+            }
+
+            pointers[map.index] = data.len() as i32;
+            for offset in offsets {
+                data.write_i32::<LittleEndian>(offset)?;
+            }
+            for length in lengths {
+                data.write_u16::<LittleEndian>(length)?;
+            }
+            data.write_u16::<LittleEndian>(map.width)?;
+            data.write_u16::<LittleEndian>(map.height)?;
+
+            // Names are NUL terminated in a 16 byte field
+            let mut name = [0u8; 16];
+            let name_len = map.name.len().min(15);
+            name[..name_len].copy_from_slice(&map.name.as_bytes()[..name_len]);
+            data.write_all(&name)?;
+        }
+
+        maphead.write_u16::<LittleEndian>(RLEW_TAG)?;
+        for pointer in pointers {
+            maphead.write_i32::<LittleEndian>(pointer)?;
+        }
+        gamemaps.write_all(&data)
+    }
+}
+
+#[derive(Clone)]
+pub struct MapHeader {
+    /// Slot of the map in MAPHEAD
+    pub index: usize,
+    pub name: String,
+    pub width: u16,
+    pub height: u16,
+    plane_offsets: [i32; 3],
+    plane_lengths: [u16; 3],
+}
+
+/// Map headers are read up front, planes are only expanded by [`MapArchive::load_map`]
+pub struct MapArchive<R = Box<dyn ReadSeek>> {
+    rlew_tag: u16,
+    headers: Vec<MapHeader>,
+    reader: R,
+    cache: Option<HashMap<usize, Gamemaps>>,
+}
+
+impl MapArchive {
+    pub fn open(path: &str, variant: GameVariant) -> Result<Self, GamemapsError> {
+        Self::from_source(&DirSource::new(path), variant)
+    }
+
+    pub fn from_source(
+        source: &dyn DataSource,
+        variant: GameVariant,
+    ) -> Result<Self, GamemapsError> {
+        let missing_file = |path: String| move |source| GamemapsError::MissingFile { path, source };
+
+        let maphead_name = variant.file_name("MAPHEAD");
+        let maphead = source
+            .read(&maphead_name)
+            .map_err(missing_file(maphead_name))?;
+        let gamemaps_name = variant.file_name("GAMEMAPS");
+        let gamemaps = source
+            .open(&gamemaps_name)
+            .map_err(missing_file(gamemaps_name))?;
+
+        Self::from_reader(&maphead, gamemaps)
+    }
+}
+
+impl<R: Read + Seek> MapArchive<R> {
+    pub fn from_reader(maphead: &[u8], mut reader: R) -> Result<Self, GamemapsError> {
+        let mut maphead = maphead;
+
+        let rlew_tag = maphead
+            .read_u16::<LittleEndian>()
+            .map_err(|_| GamemapsError::TruncatedMapHead)?;
+
+        let mut headers = vec![];
+
+        for index in 0..MAX_MAPS {
+            let ptr = maphead
+                .read_i32::<LittleEndian>()
+                .map_err(|_| GamemapsError::TruncatedMapHead)?;
+            if ptr <= 0 {
                 continue;
             }
 
-            let mut data = BufReader::new(gamemaps.get(ptr as usize..ptr as usize + 38).unwrap());
-            let off_plane0 = data.read_i32::<LittleEndian>().unwrap();
-            let off_plane1 = data.read_i32::<LittleEndian>().unwrap();
-            let off_plane2 = data.read_i32::<LittleEndian>().unwrap();
-            let len_plane0 = data.read_u16::<LittleEndian>().unwrap();
-            let len_plane1 = data.read_u16::<LittleEndian>().unwrap();
-            let len_plane2 = data.read_u16::<LittleEndian>().unwrap();
+            let mut header = [0; MAP_HEADER_SIZE];
+            reader
+                .seek(SeekFrom::Start(ptr as u64))
+                .and_then(|_| reader.read_exact(&mut header))
+                .map_err(|_| GamemapsError::TruncatedMapHeader { map: index })?;
+            let mut data = header.as_slice();
+
+            // The header is exactly 38 bytes long, so none of these reads can fail
+            let mut plane_offsets = [0; 3];
+            for offset in plane_offsets.iter_mut() {
+                *offset = data.read_i32::<LittleEndian>().unwrap();
+            }
+            let mut plane_lengths = [0; 3];
+            for length in plane_lengths.iter_mut() {
+                *length = data.read_u16::<LittleEndian>().unwrap();
+            }
             let width = data.read_u16::<LittleEndian>().unwrap();
             let height = data.read_u16::<LittleEndian>().unwrap();
-            let mut name = String::new();
-            let _ = data.read_to_string(&mut name);
-
-            let plane0 = Self::get_plane_data(&gamemaps, off_plane0, len_plane0, magic);
-            let plane1 = Self::get_plane_data(&gamemaps, off_plane1, len_plane1, magic);
-            let plane2 = Self::get_plane_data(&gamemaps, off_plane2, len_plane2, magic);
+            let name_len = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+            let name = String::from_utf8_lossy(&data[..name_len]).into_owned();
 
-            result.push(Self {
-                plane0,
-                plane1,
-                plane2,
+            headers.push(MapHeader {
+                index,
+                name,
                 width,
                 height,
-                name,
+                plane_offsets,
+                plane_lengths,
             });
         }
 
-        result
+        Ok(Self {
+            rlew_tag,
+            headers,
+            reader,
+            cache: None,
+        })
+    }
+
+    /// Headers of all maps in the archive, in slot order
+    pub fn headers(&self) -> &[MapHeader] {
+        &self.headers
+    }
+
+    pub fn header(&self, index: usize) -> Option<&MapHeader> {
+        self.headers.iter().find(|header| header.index == index)
+    }
+
+    /// Keep expanded maps around so repeated [`MapArchive::load_map`] calls are cheap
+    pub fn set_caching(&mut self, enabled: bool) {
+        if !enabled {
+            self.cache = None;
+        } else if self.cache.is_none() {
+            self.cache = Some(HashMap::new());
+        }
+    }
+
+    /// Expands all planes of the map in slot `index`
+    pub fn load_map(&mut self, index: usize) -> Result<Gamemaps, GamemapsError> {
+        if let Some(map) = self.cache.as_ref().and_then(|cache| cache.get(&index)) {
+            return Ok(map.clone());
+        }
+
+        let header = self
+            .header(index)
+            .ok_or(GamemapsError::NoSuchMap { map: index })?
+            .clone();
+
+        let map = Gamemaps {
+            index,
+            plane0: self.read_plane(&header, 0)?,
+            plane1: self.read_plane(&header, 1)?,
+            plane2: self.read_plane(&header, 2)?,
+            width: header.width,
+            height: header.height,
+            name: header.name,
+        };
+
+        if let Some(cache) = self.cache.as_mut() {
+            cache.insert(index, map.clone());
+        }
+
+        Ok(map)
+    }
+
+    pub fn load_all(&mut self) -> Result<Vec<Gamemaps>, GamemapsError> {
+        let indices = self
+            .headers
+            .iter()
+            .map(|header| header.index)
+            .collect::<Vec<_>>();
+        indices
+            .into_iter()
+            .map(|index| self.load_map(index))
+            .collect()
     }
 
-    fn get_plane_data(data: &[u8], offset: i32, len: u16, magic: u16) -> Vec<u8> {
-        let plane_data = data
-            .get(offset as usize..offset as usize + len as usize)
-            .unwrap();
-        let plane_data = carmack_expand(plane_data);
-        let plane_data = rlew_expand(&plane_data, magic);
+    fn read_plane(&mut self, header: &MapHeader, plane: usize) -> Result<Vec<u8>, GamemapsError> {
+        let map = header.index;
+        let offset = u64::try_from(header.plane_offsets[plane])
+            .map_err(|_| GamemapsError::PlaneOutOfRange { map, plane })?;
+
+        let mut plane_data = vec![0; header.plane_lengths[plane] as usize];
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.reader.read_exact(&mut plane_data))
+            .map_err(|_| GamemapsError::PlaneOutOfRange { map, plane })?;
+
+        let plane_data = carmack_expand(&plane_data).map_err(|source| GamemapsError::Carmack {
+            map,
+            plane,
+            source,
+        })?;
 
-        plane_data
+        rlew_expand(&plane_data, self.rlew_tag).map_err(|source| GamemapsError::Rlew {
+            map,
+            plane,
+            source,
+        })
     }
 }
 
-fn carmack_expand(compressed: &[u8]) -> Vec<u8> {
+fn underrun(_: std::io::Error) -> ExpandError {
+    ExpandError::Underrun
+}
+
+pub fn carmack_expand(compressed: &[u8]) -> Result<Vec<u8>, ExpandError> {
     let mut result = Vec::new();
-    let mut buf = BufReader::new(compressed);
+    let mut buf = compressed;
 
-    let decompressed_size = buf.read_u16::<LittleEndian>().unwrap() as usize;
+    let decompressed_size = buf.read_u16::<LittleEndian>().map_err(underrun)? as usize;
     let mut length = decompressed_size / 2;
 
     while length > 0 {
-        let ch = buf.read_u16::<LittleEndian>().unwrap();
+        let ch = buf.read_u16::<LittleEndian>().map_err(underrun)?;
         let chhigh = ch >> 8;
-        if chhigh == 0xA7 {
-            let count = ch & 0xFF;
+        if chhigh == 0xA7 || chhigh == 0xA8 {
+            let count = (ch & 0xFF) as usize;
             if count == 0 {
-                let ch = buf.read_u8().unwrap();
+                // Escaped word that happens to look like a pointer
+                let ch = buf.read_u8().map_err(underrun)?;
                 result.push(ch);
+                result.push(chhigh as u8);
                 length -= 1;
-            } else {
-                let offset = buf.read_u8().unwrap() as usize;
-                let mut copyptr = result.len() - (offset * 2);
-                length -= count as usize;
-                for _ in 0..count * 2 {
-                    result.push(result[copyptr]);
-                    copyptr += 1;
-                }
+                continue;
             }
-        } else if chhigh == 0xA8 {
-            let count = ch & 0xFF;
-            if count == 0 {
-                let ch = buf.read_u8().unwrap();
-                result.push(ch);
-                length -= 1;
+
+            if count > length {
+                return Err(ExpandError::Overrun);
+            }
+
+            let mut copyptr = if chhigh == 0xA7 {
+                let offset = buf.read_u8().map_err(underrun)? as usize * 2;
+                if offset == 0 || offset > result.len() {
+                    return Err(ExpandError::BadReference);
+                }
+                result.len() - offset
             } else {
-                let offset = buf.read_u16::<LittleEndian>().unwrap() as usize;
-                let mut copyptr = offset * 2;
-                length -= count as usize;
-                for _ in 0..count * 2 {
-                    result.push(result[copyptr]);
-                    copyptr += 1;
+                let offset = buf.read_u16::<LittleEndian>().map_err(underrun)? as usize * 2;
+                if offset >= result.len() {
+                    return Err(ExpandError::BadReference);
                 }
+                offset
+            };
+
+            length -= count;
+            for _ in 0..count * 2 {
+                result.push(result[copyptr]);
+                copyptr += 1;
             }
         } else {
             result.push((ch & 0xFF) as u8);
@@ -119,25 +432,27 @@ fn carmack_expand(compressed: &[u8]) -> Vec<u8> {
         }
     }
 
-    assert_eq!(result.len(), decompressed_size);
+    if result.len() != decompressed_size {
+        return Err(ExpandError::Underrun);
+    }
 
-    result
+    Ok(result)
 }
 
-fn rlew_expand(compressed: &[u8], rlewtag: u16) -> Vec<u8> {
+pub fn rlew_expand(compressed: &[u8], rlewtag: u16) -> Result<Vec<u8>, ExpandError> {
     let mut result = Vec::new();
-    let mut buf = BufReader::new(compressed);
+    let mut buf = compressed;
 
-    let decompressed_size = buf.read_u16::<LittleEndian>().unwrap() as usize;
+    let decompressed_size = buf.read_u16::<LittleEndian>().map_err(underrun)? as usize;
 
     while result.len() < decompressed_size {
-        let value = buf.read_u16::<LittleEndian>().unwrap();
+        let value = buf.read_u16::<LittleEndian>().map_err(underrun)?;
         if value != rlewtag {
             result.push((value & 0xFF) as u8);
             result.push((value >> 8) as u8);
         } else {
-            let count = buf.read_u16::<LittleEndian>().unwrap();
-            let value = buf.read_u16::<LittleEndian>().unwrap();
+            let count = buf.read_u16::<LittleEndian>().map_err(underrun)?;
+            let value = buf.read_u16::<LittleEndian>().map_err(underrun)?;
             for _ in 0..count {
                 result.push((value & 0xFF) as u8);
                 result.push((value >> 8) as u8);
@@ -145,9 +460,138 @@ fn rlew_expand(compressed: &[u8], rlewtag: u16) -> Vec<u8> {
         }
     }
 
-    assert_eq!(result.len(), decompressed_size);
+    if result.len() != decompressed_size {
+        return Err(ExpandError::Overrun);
+    }
 
-    result
+    Ok(result)
+}
+
+/// Carmack-compresses `data`, which must have an even length, the inverse of [`carmack_expand`]
+pub fn carmack_compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    const NEAR_TAG: u8 = 0xA7;
+    const FAR_TAG: u8 = 0xA8;
+    const MAX_COUNT: usize = 0xFF;
+    const MAX_FAR_CANDIDATES: usize = 256;
+
+    let words = data
+        .chunks_exact(2)
+        .map(|w| u16::from_le_bytes([w[0], w[1]]))
+        .collect::<Vec<_>>();
+
+    let mut result = Vec::new();
+    result.extend_from_slice(&expanded_length(&words)?.to_le_bytes());
+
+    let mut word_pairs: HashMap<(u16, u16), Vec<usize>> = HashMap::new();
+
+    let mut i = 0;
+    while i < words.len() {
+        let start_i = i;
+        let max_count = (words.len() - i).min(MAX_COUNT);
+
+        // Copies may overlap the words being produced, the expander copies forwards
+        let match_len = |start: usize| {
+            (0..max_count)
+                .take_while(|&n| words[start + n] == words[i + n])
+                .count()
+        };
+
+        let (mut near_len, mut near_pos) = (0, 0);
+        for start in i.saturating_sub(MAX_COUNT)..i {
+            let len = match_len(start);
+            if len >= near_len {
+                (near_len, near_pos) = (len, start);
+            }
+        }
+
+        // Far pointers only pay off from three words, so only look where two already match
+        let (mut far_len, mut far_pos) = (0, 0);
+        if let Some(starts) = words
+            .get(i + 1)
+            .and_then(|&w| word_pairs.get(&(words[i], w)))
+        {
+            for &start in starts.iter().rev().take(MAX_FAR_CANDIDATES) {
+                let len = match_len(start);
+                if len > far_len {
+                    (far_len, far_pos) = (len, start);
+                }
+            }
+        }
+
+        // A near pointer costs 3 bytes and a far pointer 4, a literal word 2
+        if near_len >= 2 && near_len + 1 >= far_len {
+            result.extend_from_slice(&[near_len as u8, NEAR_TAG, (i - near_pos) as u8]);
+            i += near_len;
+        } else if far_len >= 3 {
+            result.extend_from_slice(&[far_len as u8, FAR_TAG]);
+            result.extend_from_slice(&(far_pos as u16).to_le_bytes());
+            i += far_len;
+        } else {
+            let [low, high] = words[i].to_le_bytes();
+            if high == NEAR_TAG || high == FAR_TAG {
+                // Escape words that would otherwise be read as pointers
+                result.extend_from_slice(&[0, high, low]);
+            } else {
+                result.extend_from_slice(&[low, high]);
+            }
+            i += 1;
+        }
+
+        for start in start_i..i.min(words.len() - 1) {
+            word_pairs
+                .entry((words[start], words[start + 1]))
+                .or_default()
+                .push(start);
+        }
+    }
+
+    Ok(result)
+}
+
+/// RLEW-compresses `data`, which must have an even length, the inverse of [`rlew_expand`]
+pub fn rlew_compress(data: &[u8], rlewtag: u16) -> std::io::Result<Vec<u8>> {
+    let words = data
+        .chunks_exact(2)
+        .map(|w| u16::from_le_bytes([w[0], w[1]]))
+        .collect::<Vec<_>>();
+
+    let mut result = Vec::new();
+    result.extend_from_slice(&expanded_length(&words)?.to_le_bytes());
+
+    let mut i = 0;
+    while i < words.len() {
+        let value = words[i];
+        let count = words[i..]
+            .iter()
+            .take(u16::MAX as usize)
+            .take_while(|&&w| w == value)
+            .count();
+
+        // Runs only pay off from four words, but the tag itself must always be escaped
+        if count > 3 || value == rlewtag {
+            for word in [rlewtag, count as u16, value] {
+                result.extend_from_slice(&word.to_le_bytes());
+            }
+        } else {
+            for _ in 0..count {
+                result.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        i += count;
+    }
+
+    Ok(result)
+}
+
+/// The length header of a compressed plane, which only holds 16 bits
+fn expanded_length(words: &[u16]) -> std::io::Result<u16> {
+    u16::try_from(words.len() * 2).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} bytes don't fit in a compressed plane", words.len() * 2),
+        )
+    })
 }
 
 #[cfg(test)]
@@ -160,7 +604,8 @@ mod tests {
             carmack_expand(&[
                 0x10, 0x00, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x02, 0xa7, 0x02, 0x01, 0x02, 0x03,
                 0x04, 0x05, 0x06,
-            ]),
+            ])
+            .unwrap(),
             vec![
                 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0xcc, 0xdd, 0xee, 0xff, 0x01, 0x02, 0x03, 0x04,
                 0x05, 0x06
@@ -174,7 +619,8 @@ mod tests {
             carmack_expand(&[
                 22, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B,
                 0x04, 0xA7, 0x06, 0x00, 0x01
-            ]),
+            ])
+            .unwrap(),
             [
                 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x00, 0x01,
                 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x00, 0x01
@@ -188,7 +634,8 @@ mod tests {
             carmack_expand(&[
                 0x10, 0x00, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x02, 0xa8, 0x01, 0x00, 0x01, 0x02,
                 0x03, 0x04, 0x05, 0x06,
-            ]),
+            ])
+            .unwrap(),
             vec![
                 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0xcc, 0xdd, 0xee, 0xff, 0x01, 0x02, 0x03, 0x04,
                 0x05, 0x06
@@ -199,7 +646,7 @@ mod tests {
     #[test]
     fn test_decompress_rlew() {
         assert_eq!(
-            rlew_expand(&[0x04, 0x00, 0xFE, 0xFE, 0x02, 0x00, 0x03, 0x04], 0xFEFE),
+            rlew_expand(&[0x04, 0x00, 0xFE, 0xFE, 0x02, 0x00, 0x03, 0x04], 0xFEFE).unwrap(),
             vec![0x03, 0x04, 0x03, 0x04]
         );
     }
@@ -207,8 +654,207 @@ mod tests {
     #[test]
     fn test_decompress_rlew_flag_word() {
         assert_eq!(
-            rlew_expand(&[0x02, 0x00, 0xFE, 0xFE, 0x01, 0x00, 0xFE, 0xFE], 0xFEFE),
+            rlew_expand(&[0x02, 0x00, 0xFE, 0xFE, 0x01, 0x00, 0xFE, 0xFE], 0xFEFE).unwrap(),
             vec![0xFE, 0xFE]
         );
     }
+
+    #[test]
+    fn test_decompress_carmack_escaped_pointer_tag() {
+        assert_eq!(
+            carmack_expand(&[0x04, 0x00, 0x00, 0xA7, 0x12, 0x34, 0x56]).unwrap(),
+            vec![0x12, 0xA7, 0x34, 0x56]
+        );
+    }
+
+    #[test]
+    fn test_decompress_carmack_underrun() {
+        assert_eq!(
+            carmack_expand(&[0x06, 0x00, 0xaa, 0xbb, 0xcc, 0xdd]),
+            Err(ExpandError::Underrun)
+        );
+    }
+
+    #[test]
+    fn test_decompress_carmack_overrun() {
+        assert_eq!(
+            carmack_expand(&[0x04, 0x00, 0xaa, 0xbb, 0x02, 0xa7, 0x01]),
+            Err(ExpandError::Overrun)
+        );
+    }
+
+    #[test]
+    fn test_decompress_carmack_bad_reference() {
+        assert_eq!(
+            carmack_expand(&[0x04, 0x00, 0xaa, 0xbb, 0x01, 0xa7, 0x02]),
+            Err(ExpandError::BadReference)
+        );
+    }
+
+    #[test]
+    fn test_decompress_rlew_overrun() {
+        assert_eq!(
+            rlew_expand(&[0x04, 0x00, 0xFE, 0xFE, 0x03, 0x00, 0x03, 0x04], 0xFEFE),
+            Err(ExpandError::Overrun)
+        );
+    }
+
+    fn test_plane(seed: u32) -> Vec<u8> {
+        // Mix of runs, repeats and words that look like carmack pointers
+        let mut state = seed;
+        (0..64 * 64)
+            .flat_map(|i| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                let word: u16 = match (state >> 16) % 6 {
+                    0 => 0xA700 | (i & 0xFF) as u16,
+                    1 => 0xA800 | (i & 0xFF) as u16,
+                    2 => RLEW_TAG,
+                    3 => (state >> 20) as u16,
+                    _ => (i / 37) as u16,
+                };
+                word.to_le_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compress_rlew_round_trip() {
+        for seed in 0..8 {
+            let plane = test_plane(seed);
+            assert_eq!(
+                rlew_expand(&rlew_compress(&plane, RLEW_TAG).unwrap(), RLEW_TAG).unwrap(),
+                plane
+            );
+        }
+        assert_eq!(
+            rlew_compress(&[0x03, 0x04].repeat(4), 0xFEFE).unwrap(),
+            [0x08, 0x00, 0xFE, 0xFE, 0x04, 0x00, 0x03, 0x04]
+        );
+
+        // The length header only holds 16 bits
+        assert!(rlew_compress(&vec![0; 0x10000], RLEW_TAG).is_err());
+        assert!(carmack_compress(&vec![0; 0x10000]).is_err());
+    }
+
+    #[test]
+    fn test_compress_carmack_round_trip() {
+        for seed in 0..8 {
+            let plane = test_plane(seed);
+            assert_eq!(
+                carmack_expand(&carmack_compress(&plane).unwrap()).unwrap(),
+                plane
+            );
+        }
+        assert_eq!(
+            carmack_compress(&[0xaa, 0xbb, 0xcc, 0xdd, 0xaa, 0xbb, 0xcc, 0xdd]).unwrap(),
+            [0x08, 0x00, 0xaa, 0xbb, 0xcc, 0xdd, 0x02, 0xa7, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let maps = (0..3)
+            .map(|i| Gamemaps {
+                index: i as usize * 2,
+                plane0: test_plane(i * 3),
+                plane1: test_plane(i * 3 + 1),
+                plane2: test_plane(i * 3 + 2),
+                width: 64,
+                height: 64,
+                name: format!("Level {}", i),
+            })
+            .collect::<Vec<_>>();
+
+        let mut maphead = Vec::new();
+        let mut gamemaps = Vec::new();
+        Gamemaps::write(&maps, &mut maphead, &mut gamemaps).unwrap();
+        assert_eq!(maphead.len(), 2 + 4 * MAX_MAPS);
+
+        let loaded = Gamemaps::from_data(&maphead, &gamemaps).unwrap();
+        assert_eq!(loaded.len(), maps.len());
+        for (loaded, map) in loaded.iter().zip(&maps) {
+            assert_eq!(loaded.plane0, map.plane0);
+            assert_eq!(loaded.plane1, map.plane1);
+            assert_eq!(loaded.plane2, map.plane2);
+            assert_eq!((loaded.width, loaded.height), (map.width, map.height));
+            assert_eq!(loaded.name, map.name);
+            assert_eq!(loaded.index, map.index);
+        }
+    }
+
+    #[test]
+    fn test_map_archive_loads_lazily() {
+        let maps = (0..2)
+            .map(|i| Gamemaps {
+                index: i + 5,
+                plane0: test_plane(i as u32),
+                plane1: vec![0; 64 * 64 * 2],
+                plane2: vec![0; 64 * 64 * 2],
+                width: 64,
+                height: 64,
+                name: format!("Level {}", i),
+            })
+            .collect::<Vec<_>>();
+
+        let mut maphead = Vec::new();
+        let mut gamemaps = Vec::new();
+        Gamemaps::write(&maps, &mut maphead, &mut gamemaps).unwrap();
+
+        // Corrupt the first plane of the first map, only loading that map should fail
+        let mut archive = MapArchive::from_reader(&maphead, Cursor::new(&mut gamemaps)).unwrap();
+        let offset = archive.header(5).unwrap().plane_offsets[0] as usize;
+        archive.reader.get_mut()[offset] = 0xFF;
+        archive.set_caching(true);
+
+        let names = archive
+            .headers()
+            .iter()
+            .map(|header| (header.index, header.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, [(5, "Level 0"), (6, "Level 1")]);
+
+        assert!(matches!(
+            archive.load_map(5),
+            Err(GamemapsError::Carmack {
+                map: 5,
+                plane: 0,
+                ..
+            })
+        ));
+        assert_eq!(archive.load_map(6).unwrap().plane0, maps[1].plane0);
+        assert!(matches!(
+            archive.load_map(7),
+            Err(GamemapsError::NoSuchMap { map: 7 })
+        ));
+    }
+
+    #[test]
+    fn test_load_from_memory_source() {
+        let map = Gamemaps {
+            index: 3,
+            plane0: test_plane(1),
+            plane1: vec![0; 64 * 64 * 2],
+            plane2: vec![0; 64 * 64 * 2],
+            width: 64,
+            height: 64,
+            name: "Memory".to_string(),
+        };
+
+        let mut maphead = Vec::new();
+        let mut gamemaps = Vec::new();
+        Gamemaps::write(std::slice::from_ref(&map), &mut maphead, &mut gamemaps).unwrap();
+
+        let mut source = crate::datasource::MemorySource::new();
+        source.insert("maphead.sod", maphead);
+        assert!(matches!(
+            Gamemaps::from_source(&source, GameVariant::Spear),
+            Err(GamemapsError::MissingFile { path, .. }) if path == "GAMEMAPS.SOD"
+        ));
+
+        source.insert("gamemaps.sod", gamemaps);
+        let maps = Gamemaps::from_source(&source, GameVariant::Spear).unwrap();
+        assert_eq!(maps.len(), 1);
+        assert_eq!(maps[0].name, "Memory");
+        assert_eq!(maps[0].plane0, map.plane0);
+    }
 }
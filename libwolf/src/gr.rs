@@ -1,180 +1,546 @@
 use std::{
-    fs::File,
-    io::{BufReader, Cursor, Read, Seek, Write},
+    io::{Cursor, Read, Write},
+    sync::{Mutex, MutexGuard},
 };
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use thiserror::Error;
 
-use crate::wl6_igrab::{self, GraphicNum};
+use crate::{
+    article::{self, Page},
+    chunkcache::{CacheStats, ChunkCache},
+    datasource::{DataSource, DirSource},
+    demo::{Demo, DemoError},
+    font::{Font, FontError},
+    grlayout::GrLayout,
+    huffman::{HuffDict, HuffError},
+    surface::{ImageError, Surface},
+    variant::GameVariant,
+    wl6_igrab::{GraphicNum, PIC_NAMES},
+};
 
 #[derive(Debug, Error)]
 pub enum GrArchiveError {
     #[error("Not a pic")]
     NotAPic,
+    #[error("There is no font {0}")]
+    NoSuchFont(usize),
+    #[error("Not a text chunk")]
+    NotAText,
+    #[error("There is no tile {0}")]
+    NoSuchTile(usize),
+    #[error("There is no demo {0}")]
+    NoSuchDemo(usize),
+    #[error("There is no chunk {0}")]
+    NoSuchChunk(usize),
+    #[error("Chunk {0} is sparse")]
+    SparseChunk(usize),
+    #[error("Chunk {0} is truncated")]
+    TruncatedChunk(usize),
+    #[error(transparent)]
+    Demo(#[from] DemoError),
+    #[error(transparent)]
+    Font(#[from] FontError),
+    #[error(transparent)]
+    Image(#[from] ImageError),
+    #[error("Pics have to be a multiple of 4 pixels wide, not {width}x{height}")]
+    InvalidPicSize { width: usize, height: usize },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid VGADICT: {0}")]
+    Dictionary(HuffError),
+    #[error("VGAHEAD lists {chunks} chunks but the layout has {expected}")]
+    LayoutMismatch { chunks: usize, expected: usize },
+    #[error("The pic table only holds {found} of {expected} pic sizes")]
+    TruncatedPicTable { found: usize, expected: usize },
 }
 
 pub struct GrArchive {
-    huff_dict: [HuffNode; 255],
+    huff_dict: HuffDict,
     gr_starts: Vec<i32>,
-    graph_reader: BufReader<File>,
+    /// All of VGAGRAPH, so that chunks can be expanded from several threads at once
+    graph: Vec<u8>,
     pic_sizes: Vec<PicSize>,
+    layout: GrLayout,
+    cache: Mutex<ChunkCache>,
 }
 
-#[derive(Clone, Copy, Default)]
-struct HuffNode {
-    bit0: u16,
-    bit1: u16,
-}
-
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PicSize {
     pub width: u16,
     pub height: u16,
 }
 
 impl GrArchive {
-    pub fn new(wolf_path: &str) -> Self {
-        let mut dict_reader =
-            BufReader::new(File::open(format!("{}/VGADICT.WL6", wolf_path)).unwrap());
-        let mut huff_dict = [HuffNode::default(); 255];
-
-        for d in huff_dict.iter_mut() {
-            d.bit0 = dict_reader.read_u16::<LittleEndian>().unwrap();
-            d.bit1 = dict_reader.read_u16::<LittleEndian>().unwrap();
-        }
+    pub fn new(wolf_path: &str, variant: GameVariant) -> Result<Self, GrArchiveError> {
+        Self::from_source(&DirSource::new(wolf_path), variant)
+    }
 
-        let mut head_reader =
-            BufReader::new(File::open(format!("{}/VGAHEAD.WL6", wolf_path)).unwrap());
+    pub fn from_source(
+        source: &dyn DataSource,
+        variant: GameVariant,
+    ) -> Result<Self, GrArchiveError> {
+        Self::with_layout(source, variant, variant.gr_layout())
+    }
 
-        let mut gr_starts = Vec::new();
+    /// Opens the graphics of `variant` with a layout other than the built-in
+    /// one, e.g. one parsed by [`GrLayout::parse`] for a modified game
+    pub fn with_layout(
+        source: &dyn DataSource,
+        variant: GameVariant,
+        layout: GrLayout,
+    ) -> Result<Self, GrArchiveError> {
+        let extension = variant.graphics_extension();
+
+        Self::from_readers(
+            source.open(&format!("VGADICT.{}", extension))?,
+            source.open(&format!("VGAHEAD.{}", extension))?,
+            source.open(&format!("VGAGRAPH.{}", extension))?,
+            layout,
+        )
+    }
 
-        for _ in 0..wl6_igrab::NUMCHUNKS + 1 {
-            let mut value = head_reader.read_u24::<LittleEndian>().unwrap() as i32;
-            if value == 0xFF_FF_FF {
-                value = -1;
-            }
-            gr_starts.push(value);
+    pub fn from_readers<D: Read, H: Read, G: Read>(
+        dict_reader: D,
+        mut head_reader: H,
+        mut graph_reader: G,
+        layout: GrLayout,
+    ) -> Result<Self, GrArchiveError> {
+        let huff_dict = HuffDict::read(dict_reader).map_err(|error| match error {
+            HuffError::Io(error) => GrArchiveError::Io(error),
+            error => GrArchiveError::Dictionary(error),
+        })?;
+
+        // A VGAHEAD of another release would have pics land on the wrong chunks
+        let mut head = Vec::new();
+        head_reader.read_to_end(&mut head)?;
+        let chunks = (head.len() / 3).saturating_sub(1);
+        if head.len() % 3 != 0 || chunks != layout.num_chunks {
+            return Err(GrArchiveError::LayoutMismatch {
+                chunks,
+                expected: layout.num_chunks,
+            });
         }
 
+        let gr_starts = head
+            .chunks_exact(3)
+            .map(
+                |start| match u32::from_le_bytes([start[0], start[1], start[2], 0]) {
+                    0xFF_FF_FF => -1,
+                    value => value as i32,
+                },
+            )
+            .collect();
+
+        let mut graph = Vec::new();
+        graph_reader.read_to_end(&mut graph)?;
+
         let mut this = GrArchive {
             pic_sizes: Vec::new(),
             huff_dict,
             gr_starts,
-            graph_reader: BufReader::new(
-                File::open(format!("{}/VGAGRAPH.WL6", wolf_path)).unwrap(),
-            ),
+            graph,
+            layout,
+            cache: Mutex::default(),
         };
 
-        let mut pic_sizes_data = Cursor::new(this.expand_chunk(0));
-        for _ in 0..wl6_igrab::NUMPICS {
-            let width = pic_sizes_data.read_u16::<LittleEndian>().unwrap();
-            let height = pic_sizes_data.read_u16::<LittleEndian>().unwrap();
+        let mut pic_sizes_data = Cursor::new(this.decode_chunk(0)?);
+        let expected = this.layout.pics.len();
+        for found in 0..expected {
+            let truncated = |_| GrArchiveError::TruncatedPicTable { found, expected };
+            let width = pic_sizes_data
+                .read_u16::<LittleEndian>()
+                .map_err(truncated)?;
+            let height = pic_sizes_data
+                .read_u16::<LittleEndian>()
+                .map_err(truncated)?;
             this.pic_sizes.push(PicSize { width, height });
         }
 
-        this
+        Ok(this)
     }
 
-    fn huff_expand<R: Read, W: Write>(&self, mut compressed_reader: R, dest_writer: &mut W) {
-        let head_node = &self.huff_dict[254];
-        let mut current_node = head_node;
+    /// Expands a chunk, or copies it out of the cache if it has been expanded before
+    pub fn expand_chunk(&self, chunk_index: usize) -> Result<Vec<u8>, GrArchiveError> {
+        if let Some(data) = self.cache().get(chunk_index) {
+            return Ok(data.to_vec());
+        }
 
-        let mut current_char = compressed_reader.read_u8().unwrap();
-        let mut bit = 1;
+        // The lock isn't held while expanding, other threads may race to
+        // expand the same chunk but won't wait for each other
+        let data = self.decode_chunk(chunk_index)?;
+        let mut cache = self.cache();
+        if cache.capacity() > 0 {
+            cache.insert(chunk_index, data.clone());
+        }
+        Ok(data)
+    }
 
-        loop {
-            let which_bit = if (current_char & bit) == bit {
-                current_node.bit1
-            } else {
-                current_node.bit0
-            };
+    fn cache(&self) -> MutexGuard<'_, ChunkCache> {
+        self.cache.lock().unwrap()
+    }
 
-            if which_bit <= 255 {
-                dest_writer.write_u8((which_bit & 0xFF) as u8).unwrap();
-                current_node = head_node;
-            } else {
-                current_node = &self.huff_dict[which_bit as usize - 256];
-            }
+    /// Keeps up to `capacity` expanded chunks around, dropping the least
+    /// recently used ones. Nothing is cached by default.
+    pub fn set_cache_capacity(&self, capacity: usize) {
+        self.cache().set_capacity(capacity);
+    }
 
-            if bit == 0x80 {
-                // We're at the end of the current byte, fetch the next one
-                if let Ok(c) = compressed_reader.read_u8() {
-                    current_char = c;
-                } else {
-                    // No more data in the input stream, we're done
-                    break;
-                }
-                bit = 1;
-            } else {
-                bit <<= 1;
+    /// Expands chunks ahead of time, e.g. a lump such as
+    /// `LATCHPICS_LUMP_START..=LATCHPICS_LUMP_END` like `CA_CacheMarks` does.
+    /// Sparse chunks are skipped and preloading doesn't count towards the stats.
+    pub fn preload<I: IntoIterator<Item = usize>>(&self, chunks: I) -> Result<(), GrArchiveError> {
+        if self.cache().capacity() == 0 {
+            return Ok(());
+        }
+
+        for chunk_index in chunks {
+            let sparse = self
+                .gr_starts
+                .get(chunk_index)
+                .is_some_and(|&start| start < 0);
+            if !sparse && !self.cache().contains(chunk_index) {
+                let data = self.decode_chunk(chunk_index)?;
+                self.cache().insert(chunk_index, data);
             }
         }
+
+        Ok(())
+    }
+
+    /// Drops all cached chunks
+    pub fn purge(&self) {
+        self.cache().purge();
     }
 
-    pub fn expand_chunk(&mut self, chunk_index: usize) -> Vec<u8> {
-        let pos = self.gr_starts[chunk_index];
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache().stats()
+    }
+
+    fn decode_chunk(&self, chunk_index: usize) -> Result<Vec<u8>, GrArchiveError> {
+        let pos = *self
+            .gr_starts
+            .get(chunk_index)
+            .ok_or(GrArchiveError::NoSuchChunk(chunk_index))?;
         if pos < 0 {
-            panic!("Sparse chunk can't be expanded");
+            return Err(GrArchiveError::SparseChunk(chunk_index));
         }
 
-        let mut next = chunk_index + 1;
-        while self.gr_starts[next] == -1 {
-            next += 1;
+        // The chunk ends where the next one that isn't sparse starts
+        let end = self.gr_starts[chunk_index + 1..]
+            .iter()
+            .find(|&&start| start >= 0)
+            .map_or(self.graph.len(), |&start| start as usize);
+        let truncated = || GrArchiveError::TruncatedChunk(chunk_index);
+        let mut compressed_reader = self.graph.get(pos as usize..end).ok_or_else(truncated)?;
+
+        // expanded sizes of tile8/16/32 are implicit
+        let expanded_size = match self.layout.implicit_size(chunk_index) {
+            Some(size) => size,
+            None => compressed_reader
+                .read_u32::<LittleEndian>()
+                .map_err(|_| truncated())? as usize,
+        };
+
+        let data = self
+            .huff_dict
+            .expand(compressed_reader, expanded_size)
+            .map_err(GrArchiveError::Dictionary)?;
+        if data.len() != expanded_size {
+            return Err(truncated());
         }
 
-        let compressed_size = self.gr_starts[next] - pos;
+        Ok(data)
+    }
 
-        self.graph_reader
-            .seek(std::io::SeekFrom::Start(pos as u64))
-            .unwrap();
+    pub fn layout(&self) -> &GrLayout {
+        &self.layout
+    }
 
-        let mut compressed_data = vec![0; compressed_size as usize];
-        self.graph_reader.read_exact(&mut compressed_data).unwrap();
-
-        let mut compressed_reader = compressed_data.as_slice();
-
-        let expanded_size =
-            if chunk_index >= wl6_igrab::STARTTILE8 && chunk_index < wl6_igrab::STARTEXTERNS {
-                //
-                // expanded sizes of tile8/16/32 are implicit
-                //
-
-                let block = 64;
-                let maskblock = 128;
-
-                if chunk_index < wl6_igrab::STARTTILE8M {
-                    block * wl6_igrab::NUMTILE8
-                } else if chunk_index < wl6_igrab::STARTTILE16 {
-                    maskblock * wl6_igrab::NUMTILE8M
-                } else if chunk_index < wl6_igrab::STARTTILE32 {
-                    maskblock * 4
-                } else if chunk_index < wl6_igrab::STARTTILE32M {
-                    block * 16
-                } else {
-                    maskblock * 16
-                }
-            } else {
-                compressed_reader.read_u32::<LittleEndian>().unwrap() as usize
-            };
+    /// Loads font `font_no`, counted from the first font chunk
+    pub fn load_font(&self, font_no: usize) -> Result<Font, GrArchiveError> {
+        if font_no >= self.layout.fonts.len() {
+            return Err(GrArchiveError::NoSuchFont(font_no));
+        }
+        let data = self.expand_chunk(self.layout.fonts.start + font_no)?;
+
+        Ok(Font::from_chunk(&data)?)
+    }
+
+    /// Parses a text chunk such as `T_HELPART` or `T_ENDART1` into pages
+    pub fn load_article(&self, chunk_index: usize) -> Result<Vec<Page>, GrArchiveError> {
+        if !self.layout.articles.contains(&chunk_index) || self.layout.demos.contains(&chunk_index)
+        {
+            return Err(GrArchiveError::NotAText);
+        }
+
+        Ok(article::parse_article(&self.expand_chunk(chunk_index)?))
+    }
+
+    /// Loads 8x8 tile `tile_no` out of the tile8 chunk, e.g. the control panel borders
+    pub fn load_tile8(&self, tile_no: usize) -> Result<Surface, GrArchiveError> {
+        if self.layout.tile8.is_empty() || tile_no >= self.layout.num_tile8 {
+            return Err(GrArchiveError::NoSuchTile(tile_no));
+        }
+        let data = self.expand_chunk(self.layout.tile8.start)?;
+        let tile = data
+            .get(tile_no * 64..(tile_no + 1) * 64)
+            .ok_or(GrArchiveError::TruncatedChunk(self.layout.tile8.start))?;
+
+        Ok(unplanarize(tile, 8, 8))
+    }
+
+    /// Loads masked 8x8 tile `tile_no`. None of the released variants have any.
+    pub fn load_tile8m(&self, tile_no: usize) -> Result<MaskedTile, GrArchiveError> {
+        if self.layout.tile8m.is_empty() || tile_no >= self.layout.num_tile8m {
+            return Err(GrArchiveError::NoSuchTile(tile_no));
+        }
+        let data = self.expand_chunk(self.layout.tile8m.start)?;
+        let (mask, image) = data
+            .get(tile_no * 128..(tile_no + 1) * 128)
+            .ok_or(GrArchiveError::TruncatedChunk(self.layout.tile8m.start))?
+            .split_at(64);
+
+        Ok(MaskedTile {
+            image: unplanarize(image, 8, 8),
+            transparent: unplanarize(mask, 8, 8)
+                .pixels
+                .into_iter()
+                .map(|mask| mask != 0)
+                .collect(),
+        })
+    }
+
+    /// Loads demo `demo_no`, counted from `T_DEMO0`
+    pub fn load_demo(&self, demo_no: usize) -> Result<Demo, GrArchiveError> {
+        if demo_no >= self.layout.demos.len() {
+            return Err(GrArchiveError::NoSuchDemo(demo_no));
+        }
+        let data = self.expand_chunk(self.layout.demos.start + demo_no)?;
 
-        let mut dest = Vec::with_capacity(expanded_size);
-        self.huff_expand(compressed_reader, &mut dest);
+        Ok(Demo::from_chunk(&data)?)
+    }
 
-        dest
+    /// Loads a pic of Wolfenstein 3D, see [`GrArchive::load_pic_chunk`] for other variants
+    pub fn load_pic(&self, pic_no: GraphicNum) -> Result<Pic, GrArchiveError> {
+        self.load_pic_chunk(pic_no as usize)
     }
 
-    pub fn load_pic(&mut self, pic_no: GraphicNum) -> Result<Pic, GrArchiveError> {
-        let chunk_index = pic_no as usize;
-        if !(wl6_igrab::STARTPICS..wl6_igrab::STARTPICM).contains(&chunk_index) {
+    pub fn load_pic_chunk(&self, chunk_index: usize) -> Result<Pic, GrArchiveError> {
+        if !self.layout.pics.contains(&chunk_index) {
             return Err(GrArchiveError::NotAPic);
         }
-        let data = self.expand_chunk(chunk_index);
+        let data = self.expand_chunk(chunk_index)?;
 
-        let size = self.pic_sizes[chunk_index - wl6_igrab::STARTPICS];
+        let size = self.pic_sizes[chunk_index - self.layout.pics.start];
 
         Ok(Pic { data, size })
     }
+
+    /// Writes every pic to `path` as an indexed PNG, decoding them on all
+    /// cores. Wolfenstein 3D pics are named after their [`GraphicNum`], e.g.
+    /// `TITLEPIC.png`, those of other layouts after their chunk, e.g. `PIC003.png`.
+    pub fn export_pics(&self, path: &str, palette: &[u8]) -> Result<(), GrArchiveError> {
+        let chunks: Vec<usize> = self
+            .layout
+            .pics
+            .clone()
+            .filter(|&chunk_index| self.gr_starts[chunk_index] >= 0)
+            .collect();
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let per_thread = chunks.len().div_ceil(threads).max(1);
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = chunks
+                .chunks(per_thread)
+                .map(|chunks| {
+                    scope.spawn(move || {
+                        chunks.iter().try_for_each(|&chunk_index| {
+                            self.export_pic(chunk_index, path, palette)
+                        })
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .try_for_each(|worker| worker.join().unwrap())
+        })
+    }
+
+    fn export_pic(
+        &self,
+        chunk_index: usize,
+        path: &str,
+        palette: &[u8],
+    ) -> Result<(), GrArchiveError> {
+        let name = if self.layout == GrLayout::WL6 {
+            PIC_NAMES[chunk_index - self.layout.pics.start].to_string()
+        } else {
+            format!("PIC{:03}", chunk_index)
+        };
+        let file = std::fs::File::create(format!("{}/{}.png", path, name))?;
+        self.load_pic_chunk(chunk_index)?
+            .to_indexed()
+            .write_png(std::io::BufWriter::new(file), palette)?;
+
+        Ok(())
+    }
+}
+
+/// Assembles the chunks of a graphics archive and writes VGADICT, VGAHEAD and VGAGRAPH
+pub struct GrWriter {
+    layout: GrLayout,
+    pic_sizes: Vec<PicSize>,
+    /// `None` for sparse chunks. Chunk 0 is written from `pic_sizes` instead.
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl GrWriter {
+    pub fn new(layout: GrLayout) -> Self {
+        Self {
+            pic_sizes: vec![
+                PicSize {
+                    width: 0,
+                    height: 0
+                };
+                layout.pics.len()
+            ],
+            chunks: vec![None; layout.num_chunks],
+            layout,
+        }
+    }
+
+    /// Starts out with every chunk of `archive`, to replace some of them
+    pub fn from_archive(archive: &GrArchive) -> Result<Self, GrArchiveError> {
+        let chunks = (0..archive.layout.num_chunks)
+            .map(|chunk_index| {
+                (archive.gr_starts[chunk_index] >= 0)
+                    .then(|| archive.expand_chunk(chunk_index))
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            layout: archive.layout.clone(),
+            pic_sizes: archive.pic_sizes.clone(),
+            chunks,
+        })
+    }
+
+    /// Replaces the expanded data of a chunk, `None` leaves it sparse
+    pub fn set_chunk(&mut self, chunk_index: usize, data: Option<Vec<u8>>) {
+        self.chunks[chunk_index] = data;
+    }
+
+    pub fn set_pic(&mut self, chunk_index: usize, pic: Pic) -> Result<(), GrArchiveError> {
+        if !self.layout.pics.contains(&chunk_index) {
+            return Err(GrArchiveError::NotAPic);
+        }
+        self.pic_sizes[chunk_index - self.layout.pics.start] = pic.size;
+        self.chunks[chunk_index] = Some(pic.data);
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str, variant: GameVariant) -> std::io::Result<()> {
+        let mut dict = Vec::new();
+        let mut head = Vec::new();
+        let mut graph = Vec::new();
+        self.write(&mut dict, &mut head, &mut graph)?;
+
+        let extension = variant.graphics_extension();
+        std::fs::write(format!("{}/VGADICT.{}", path, extension), dict)?;
+        std::fs::write(format!("{}/VGAHEAD.{}", path, extension), head)?;
+        std::fs::write(format!("{}/VGAGRAPH.{}", path, extension), graph)
+    }
+
+    /// Compresses all chunks with a dictionary built for them
+    pub fn write<D: Write, H: Write, G: Write>(
+        &self,
+        dict_writer: D,
+        mut head_writer: H,
+        mut graph_writer: G,
+    ) -> std::io::Result<()> {
+        let mut pic_table = Vec::with_capacity(self.pic_sizes.len() * 4);
+        for size in &self.pic_sizes {
+            pic_table.write_u16::<LittleEndian>(size.width)?;
+            pic_table.write_u16::<LittleEndian>(size.height)?;
+        }
+        let chunks: Vec<Option<&[u8]>> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .map(|(chunk_index, data)| match chunk_index {
+                0 => Some(pic_table.as_slice()),
+                _ => data.as_deref(),
+            })
+            .collect();
+
+        let huff_dict = HuffDict::for_data(chunks.iter().flatten().copied());
+        huff_dict.write(dict_writer)?;
+
+        let mut pos = 0;
+        for (chunk_index, data) in chunks.into_iter().enumerate() {
+            let Some(data) = data else {
+                head_writer.write_u24::<LittleEndian>(0xFF_FF_FF)?;
+                continue;
+            };
+
+            let mut compressed = Vec::new();
+            match self.layout.implicit_size(chunk_index) {
+                Some(size) if size != data.len() => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Chunk {} holds {} bytes but has to hold {}",
+                            chunk_index,
+                            data.len(),
+                            size
+                        ),
+                    ));
+                }
+                Some(_) => {}
+                None => compressed.write_u32::<LittleEndian>(data.len() as u32)?,
+            }
+            compressed.extend(huff_dict.compress(data));
+
+            head_writer.write_u24::<LittleEndian>(pos)?;
+            graph_writer.write_all(&compressed)?;
+            pos += compressed.len() as u32;
+            if pos >= 0xFF_FF_FF {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "VGAGRAPH would outgrow the 24 bit offsets of VGAHEAD",
+                ));
+            }
+        }
+        // The end of the last chunk
+        head_writer.write_u24::<LittleEndian>(pos)
+    }
+}
+
+/// Converts Mode X planar data, where each plane holds every fourth column,
+/// to a linear image
+fn unplanarize(data: &[u8], width: usize, height: usize) -> Surface {
+    let plane_size = width * height / 4;
+    let mut image = Surface::new(width, height);
+
+    for (i, pixel) in image.pixels.iter_mut().enumerate() {
+        let (x, y) = (i % width, i / width);
+        let src = (x & 3) * plane_size + y * (width / 4) + x / 4;
+        *pixel = data.get(src).copied().unwrap_or_default();
+    }
+
+    image
+}
+
+/// An image with a mask of the pixels to leave out, like the masked tiles,
+/// whose mask comes before the colours, or the sprites of VSWAP
+pub struct MaskedTile {
+    pub image: Surface,
+    /// One entry per pixel of `image`
+    pub transparent: Vec<bool>,
 }
 
 pub struct Pic {
@@ -183,23 +549,292 @@ pub struct Pic {
 }
 
 impl Pic {
-    pub fn draw(&self, output_buffer: &mut [u8]) {
-        let quater_width = self.size.width / 4;
-        let plane_size = (self.size.width as usize * self.size.height as usize) / 4;
-        let mut i = 0;
-
-        for y in 0..self.size.height as usize {
-            let dst_index_y = y * 320;
-
-            for x in 0..quater_width as usize {
-                let dst_index = dst_index_y + x * 4;
-
-                output_buffer[dst_index] = self.data[i];
-                output_buffer[dst_index + 1] = self.data[i + plane_size];
-                output_buffer[dst_index + 2] = self.data[i + plane_size * 2];
-                output_buffer[dst_index + 3] = self.data[i + plane_size * 3];
-                i += 1;
-            }
+    /// Splits a row-major image into the four planes pics are stored as
+    pub fn from_indexed(image: &Surface) -> Result<Self, GrArchiveError> {
+        if !image.width.is_multiple_of(4)
+            || image.width > u16::MAX as usize
+            || image.height > u16::MAX as usize
+        {
+            return Err(GrArchiveError::InvalidPicSize {
+                width: image.width,
+                height: image.height,
+            });
+        }
+
+        let plane_size = image.width * image.height / 4;
+        let mut data = vec![0; image.pixels.len()];
+        for (i, &pixel) in image.pixels.iter().enumerate() {
+            let (x, y) = (i % image.width, i / image.width);
+            data[(x & 3) * plane_size + y * (image.width / 4) + x / 4] = pixel;
+        }
+
+        Ok(Pic {
+            size: PicSize {
+                width: image.width as u16,
+                height: image.height as u16,
+            },
+            data,
+        })
+    }
+
+    /// Reads an indexed PNG as a pic, matching its colours to the nearest ones
+    /// of a 6-bit VGA palette such as [`crate::GAMEPAL`]
+    pub fn read_png<R: Read>(reader: R, palette: &[u8]) -> Result<Self, GrArchiveError> {
+        Self::from_indexed(&Surface::read_png(reader, palette)?)
+    }
+
+    pub fn to_indexed(&self) -> Surface {
+        unplanarize(
+            &self.data,
+            self.size.width as usize,
+            self.size.height as usize,
+        )
+    }
+
+    /// Expands to row-major 8-bit RGBA using a 6-bit VGA palette
+    pub fn to_rgba(&self, palette: &[u8]) -> Vec<u8> {
+        self.to_indexed().to_rgba(palette)
+    }
+
+    /// Draws the pic with its top left corner at `(x, y)`, clipped to the surface
+    pub fn draw(&self, surface: &mut Surface, x: i32, y: i32) {
+        surface.draw(&self.to_indexed(), x, y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Archives are shared between threads
+    fn _assert_send_sync() {
+        fn _assert<T: Send + Sync>() {}
+        _assert::<GrArchive>();
+    }
+
+    #[test]
+    fn test_wrong_layout_is_an_error() {
+        let dict = vec![0; 255 * 4];
+        let head = vec![0; 140 * 3];
+
+        assert!(matches!(
+            GrArchive::from_readers(dict.as_slice(), head.as_slice(), &[][..], GrLayout::WL6),
+            Err(GrArchiveError::LayoutMismatch {
+                chunks: 139,
+                expected: 149
+            })
+        ));
+
+        // The pic table runs past the end of VGAGRAPH
+        let layout = GrLayout {
+            num_chunks: 1,
+            ..Default::default()
+        };
+        let head = [0, 0, 0, 10, 0, 0];
+        assert!(matches!(
+            GrArchive::from_readers(dict.as_slice(), head.as_slice(), &[0u8; 3][..], layout),
+            Err(GrArchiveError::TruncatedChunk(0))
+        ));
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let mut layout = GrLayout::SDM;
+        layout.num_chunks = 6;
+        layout.pics = 1..3;
+        layout.tile8 = 3..4;
+        layout.num_tile8 = 2;
+        layout.externs = 4..6;
+        layout.demos = 4..5;
+        layout.articles = 4..6;
+
+        let pic = Pic {
+            size: PicSize {
+                width: 4,
+                height: 2,
+            },
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        };
+        let mut writer = GrWriter::new(layout.clone());
+        writer.set_pic(1, pic).unwrap();
+        writer.set_chunk(3, Some((0..128).collect()));
+        writer.set_chunk(5, Some(b"^P\nHello^E".to_vec()));
+        assert!(matches!(
+            writer.set_pic(
+                3,
+                Pic {
+                    size: PicSize {
+                        width: 0,
+                        height: 0
+                    },
+                    data: Vec::new()
+                }
+            ),
+            Err(GrArchiveError::NotAPic)
+        ));
+
+        let write = |writer: &GrWriter| {
+            let (mut dict, mut head, mut graph) = (Vec::new(), Vec::new(), Vec::new());
+            writer.write(&mut dict, &mut head, &mut graph).unwrap();
+            assert_eq!(dict.len(), 255 * 4);
+            assert_eq!(head.len(), 7 * 3);
+            // Chunks 2 and 4 are sparse
+            assert_eq!(head[6..9], [0xFF; 3]);
+            assert_eq!(head[12..15], [0xFF; 3]);
+            GrArchive::from_readers(
+                dict.as_slice(),
+                head.as_slice(),
+                graph.as_slice(),
+                layout.clone(),
+            )
+            .unwrap()
+        };
+
+        let archive = write(&writer);
+        let pic = archive.load_pic_chunk(1).unwrap();
+        assert_eq!((pic.size.width, pic.size.height), (4, 2));
+        assert_eq!(pic.data, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(archive.pic_sizes[1].width, 0);
+        assert_eq!(
+            archive.expand_chunk(3).unwrap(),
+            (0..128).collect::<Vec<u8>>()
+        );
+        assert_eq!(archive.expand_chunk(5).unwrap(), b"^P\nHello^E");
+        assert_eq!(archive.load_article(5).unwrap().len(), 1);
+        // Demos and pics sit among the articles or externs but aren't texts
+        for chunk_index in [1, 4] {
+            assert!(matches!(
+                archive.load_article(chunk_index),
+                Err(GrArchiveError::NotAText)
+            ));
+        }
+
+        let dir = std::env::temp_dir().join(format!("libwolf-gr-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        archive
+            .export_pics(dir.to_str().unwrap(), crate::GAMEPAL)
+            .unwrap();
+        assert!(dir.join("PIC001.png").exists());
+        // Sparse pics are left out
+        assert!(!dir.join("PIC002.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Recompressing what was read gives back the same chunks
+        let rewritten = write(&GrWriter::from_archive(&archive).unwrap());
+        for chunk_index in [0, 1, 3, 5] {
+            assert_eq!(
+                rewritten.expand_chunk(chunk_index).unwrap(),
+                archive.expand_chunk(chunk_index).unwrap()
+            );
         }
     }
+
+    #[test]
+    fn test_png_round_trip() {
+        let mut image = Surface::new(8, 2);
+        image.set_pixel(1, 0, 15);
+        image.set_pixel(6, 1, 4);
+
+        let pic = Pic::from_indexed(&image).unwrap();
+        // Column 1 is the first byte of the second plane
+        assert_eq!(pic.data[4], 15);
+        assert_eq!(pic.to_indexed(), image);
+        assert_eq!(pic.to_rgba(crate::GAMEPAL)[4..8], [0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let mut png = Vec::new();
+        image.write_png(&mut png, crate::GAMEPAL).unwrap();
+        let imported = Pic::read_png(png.as_slice(), crate::GAMEPAL).unwrap();
+        assert_eq!(imported.data, pic.data);
+
+        assert!(matches!(
+            Pic::from_indexed(&Surface::new(6, 2)),
+            Err(GrArchiveError::InvalidPicSize {
+                width: 6,
+                height: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_load_tile8() {
+        // A dictionary whose root decodes every bit to a byte of its own value
+        let mut dict = vec![0; 255 * 4];
+        dict[254 * 4 + 2] = 1;
+        let head = [0, 0, 0, 5, 0, 0, 21, 0, 0];
+        // An empty pic table, then two tiles of 64 bits each
+        let mut graph = vec![0; 5 + 16];
+        graph[5 + 8] = 1 << 5; // plane 0, row 2, x 4 of tile 1
+        graph[5 + 10] = 1; // plane 1, row 0, x 1 of tile 1
+
+        let layout = GrLayout {
+            num_chunks: 2,
+            tile8: 1..2,
+            num_tile8: 2,
+            ..Default::default()
+        };
+        let open = |head: &[u8], layout: GrLayout| {
+            GrArchive::from_readers(dict.as_slice(), head, graph.as_slice(), layout).unwrap()
+        };
+        let archive = open(&head, layout.clone());
+
+        let tile = archive.load_tile8(1).unwrap();
+        assert_eq!((tile.width, tile.height), (8, 8));
+        assert_eq!(tile.pixels[1], 1);
+        assert_eq!(tile.pixels[2 * 8 + 4], 1);
+        assert_eq!(tile.pixels.iter().filter(|&&p| p != 0).count(), 2);
+        assert!(archive
+            .load_tile8(0)
+            .unwrap()
+            .pixels
+            .iter()
+            .all(|&p| p == 0));
+        // Tiles can be loaded from several threads sharing the archive
+        std::thread::scope(|scope| {
+            let other = scope.spawn(|| archive.load_tile8(1).unwrap());
+            assert_eq!(archive.load_tile8(1).unwrap(), other.join().unwrap());
+        });
+
+        assert!(matches!(
+            archive.load_tile8(2),
+            Err(GrArchiveError::NoSuchTile(2))
+        ));
+
+        // A chunk holding fewer tiles than the layout lists, or none at all
+        let short = open(
+            &head,
+            GrLayout {
+                num_tile8: 3,
+                ..layout.clone()
+            },
+        );
+        assert!(matches!(
+            short.load_tile8(2),
+            Err(GrArchiveError::TruncatedChunk(1))
+        ));
+        let sparse = open(&[0, 0, 0, 0xFF, 0xFF, 0xFF, 21, 0, 0], layout);
+        assert!(matches!(
+            sparse.load_tile8(0),
+            Err(GrArchiveError::SparseChunk(1))
+        ));
+
+        let misses = archive.cache_stats().misses;
+        archive.set_cache_capacity(4);
+        archive.preload(0..2).unwrap();
+        archive.load_tile8(1).unwrap();
+        archive.load_tile8(0).unwrap();
+        assert_eq!(archive.cache_stats(), CacheStats { hits: 2, misses });
+        archive.purge();
+        archive.load_tile8(1).unwrap();
+        assert_eq!(
+            archive.cache_stats(),
+            CacheStats {
+                hits: 2,
+                misses: misses + 1
+            }
+        );
+        assert!(matches!(
+            archive.load_tile8m(0),
+            Err(GrArchiveError::NoSuchTile(0))
+        ));
+    }
 }
@@ -0,0 +1,371 @@
+use std::{collections::HashMap, ops::Range};
+
+use thiserror::Error;
+
+use crate::wl6_igrab;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GrLayoutError {
+    #[error("The header does not define {0}")]
+    MissingDefine(String),
+    #[error("{name} is defined as {value}, which is not a number")]
+    InvalidValue { name: String, value: String },
+}
+
+/// Where each kind of graphic lives in VGAGRAPH, as described by the
+/// `START*`/`NUM*` defines of an IGRAB header
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GrLayout {
+    pub num_chunks: usize,
+    pub fonts: Range<usize>,
+    pub fontm: Range<usize>,
+    pub pics: Range<usize>,
+    pub picm: Range<usize>,
+    pub sprites: Range<usize>,
+    /// All 8x8 tiles share a single chunk
+    pub tile8: Range<usize>,
+    pub tile8m: Range<usize>,
+    pub tile16: Range<usize>,
+    pub tile16m: Range<usize>,
+    pub tile32: Range<usize>,
+    pub tile32m: Range<usize>,
+    pub externs: Range<usize>,
+    /// The `T_DEMO*` chunks among the externs
+    pub demos: Range<usize>,
+    /// The `T_HELPART` and `T_ENDART*` texts among the externs, which take in
+    /// the demos in Wolfenstein 3D. Spear of Destiny has none.
+    pub articles: Range<usize>,
+    pub num_tile8: usize,
+    pub num_tile8m: usize,
+}
+
+impl GrLayout {
+    /// Wolfenstein 3D 1.4, shareware and registered
+    pub const WL6: GrLayout = GrLayout {
+        num_chunks: wl6_igrab::NUMCHUNKS,
+        fonts: wl6_igrab::STARTFONT..wl6_igrab::STARTFONT + wl6_igrab::NUMFONT,
+        fontm: wl6_igrab::STARTFONTM..wl6_igrab::STARTFONTM + wl6_igrab::NUMFONTM,
+        pics: wl6_igrab::STARTPICS..wl6_igrab::STARTPICS + wl6_igrab::NUMPICS,
+        picm: wl6_igrab::STARTPICM..wl6_igrab::STARTPICM + wl6_igrab::NUMPICM,
+        sprites: wl6_igrab::STARTSPRITES..wl6_igrab::STARTSPRITES + wl6_igrab::NUMSPRITES,
+        tile8: wl6_igrab::STARTTILE8..wl6_igrab::STARTTILE8M,
+        tile8m: wl6_igrab::STARTTILE8M..wl6_igrab::STARTTILE16,
+        tile16: wl6_igrab::STARTTILE16..wl6_igrab::STARTTILE16 + wl6_igrab::NUMTILE16,
+        tile16m: wl6_igrab::STARTTILE16M..wl6_igrab::STARTTILE16M + wl6_igrab::NUMTILE16M,
+        tile32: wl6_igrab::STARTTILE32..wl6_igrab::STARTTILE32 + wl6_igrab::NUMTILE32,
+        tile32m: wl6_igrab::STARTTILE32M..wl6_igrab::STARTTILE32M + wl6_igrab::NUMTILE32M,
+        externs: wl6_igrab::STARTEXTERNS..wl6_igrab::STARTEXTERNS + wl6_igrab::NUMEXTERNS,
+        demos: wl6_igrab::Texts::T_DEMO0 as usize..wl6_igrab::Texts::T_DEMO3 as usize + 1,
+        articles: wl6_igrab::Texts::T_HELPART as usize..wl6_igrab::Texts::T_ENDART6 as usize + 1,
+        num_tile8: wl6_igrab::NUMTILE8,
+        num_tile8m: wl6_igrab::NUMTILE8M,
+    };
+
+    /// Spear of Destiny and both mission packs
+    pub const SOD: GrLayout = GrLayout {
+        num_chunks: 169,
+        fonts: 1..3,
+        fontm: 3..3,
+        pics: 3..150,
+        picm: 150..150,
+        sprites: 150..150,
+        tile8: 150..151,
+        tile8m: 151..151,
+        tile16: 151..151,
+        tile16m: 151..151,
+        tile32: 151..151,
+        tile32m: 151..151,
+        externs: 151..169,
+        demos: 164..168,
+        articles: 169..169,
+        num_tile8: 72,
+        num_tile8m: 0,
+    };
+
+    /// The Spear of Destiny demo, which lacks the ending and id guys pics
+    pub const SDM: GrLayout = GrLayout {
+        num_chunks: 133,
+        fonts: 1..3,
+        fontm: 3..3,
+        pics: 3..130,
+        picm: 130..130,
+        sprites: 130..130,
+        tile8: 130..131,
+        tile8m: 131..131,
+        tile16: 131..131,
+        tile16m: 131..131,
+        tile32: 131..131,
+        tile32m: 131..131,
+        externs: 131..133,
+        demos: 132..133,
+        articles: 133..133,
+        num_tile8: 72,
+        num_tile8m: 0,
+    };
+
+    /// Reads the `NUM*` and `START*` defines of an IGRAB header, either the C
+    /// `GFXV_*.H` or the assembler `GFXV_*.EQU`. The demos and articles are only
+    /// listed in the `graphicnums` enum of the C header, they are left empty otherwise.
+    pub fn parse(header: &str) -> Result<Self, GrLayoutError> {
+        let mut defines = HashMap::new();
+        // The value of the next enum entry, while inside an enum
+        let mut next_entry = None;
+        let mut demos = Vec::new();
+        let mut articles = Vec::new();
+
+        for line in header.lines() {
+            let line = line.split("//").next().unwrap_or_default();
+            let line = line.split(';').next().unwrap_or_default();
+
+            if line.contains("enum") {
+                next_entry = Some(0);
+                continue;
+            }
+            if line.trim_start().starts_with('}') {
+                next_entry = None;
+                continue;
+            }
+            if let Some(next) = next_entry.as_mut() {
+                for entry in line.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                    let (name, value) = match entry.split_once('=') {
+                        Some((name, value)) => {
+                            let value = parse_number(value.trim()).ok_or_else(|| {
+                                GrLayoutError::InvalidValue {
+                                    name: name.trim().to_string(),
+                                    value: value.trim().to_string(),
+                                }
+                            })?;
+                            (name.trim(), value)
+                        }
+                        None => (entry, *next),
+                    };
+                    if name.starts_with("T_DEMO") {
+                        demos.push(value);
+                    } else if name.starts_with("T_") {
+                        articles.push(value);
+                    }
+                    *next = value + 1;
+                }
+                continue;
+            }
+
+            let (name, value) = if let Some(define) = line.trim().strip_prefix("#define") {
+                match define.split_whitespace().collect::<Vec<_>>()[..] {
+                    [name, value] => (name, value),
+                    _ => continue,
+                }
+            } else {
+                match line.split_once('=') {
+                    Some((name, value)) => (name.trim(), value.trim()),
+                    None => continue,
+                }
+            };
+
+            if name.starts_with("NUM") || name.starts_with("START") {
+                defines.insert(name.to_string(), value.to_string());
+            }
+        }
+
+        let define = |name: &str| -> Result<usize, GrLayoutError> {
+            let value = defines
+                .get(name)
+                .ok_or_else(|| GrLayoutError::MissingDefine(name.to_string()))?;
+            parse_number(value).ok_or_else(|| GrLayoutError::InvalidValue {
+                name: name.to_string(),
+                value: value.clone(),
+            })
+        };
+        let range = |kind: &str| -> Result<Range<usize>, GrLayoutError> {
+            let start = define(&format!("START{}", kind))?;
+            Ok(start..start + define(&format!("NUM{}", kind))?)
+        };
+        // All 8x8 tiles are in one chunk, which is left out when there are none
+        let tile8_range = |kind: &str| -> Result<Range<usize>, GrLayoutError> {
+            let start = define(&format!("START{}", kind))?;
+            Ok(start..start + (define(&format!("NUM{}", kind))? > 0) as usize)
+        };
+
+        Ok(GrLayout {
+            num_chunks: define("NUMCHUNKS")?,
+            fonts: range("FONT")?,
+            fontm: range("FONTM")?,
+            pics: range("PICS")?,
+            picm: range("PICM")?,
+            sprites: range("SPRITES")?,
+            tile8: tile8_range("TILE8")?,
+            tile8m: tile8_range("TILE8M")?,
+            tile16: range("TILE16")?,
+            tile16m: range("TILE16M")?,
+            tile32: range("TILE32")?,
+            tile32m: range("TILE32M")?,
+            externs: range("EXTERNS")?,
+            demos: span(&demos),
+            articles: span(&articles),
+            num_tile8: define("NUMTILE8")?,
+            num_tile8m: define("NUMTILE8M")?,
+        })
+    }
+
+    /// Expanded size of the tile chunks, which unlike every other chunk
+    /// don't store it in front of the compressed data
+    pub fn implicit_size(&self, chunk_index: usize) -> Option<usize> {
+        let block = 64;
+        let maskblock = 128;
+
+        if self.tile8.contains(&chunk_index) {
+            Some(block * self.num_tile8)
+        } else if self.tile8m.contains(&chunk_index) {
+            Some(maskblock * self.num_tile8m)
+        } else if self.tile16.contains(&chunk_index) {
+            Some(block * 4)
+        } else if self.tile16m.contains(&chunk_index) {
+            Some(maskblock * 4)
+        } else if self.tile32.contains(&chunk_index) {
+            Some(block * 16)
+        } else if self.tile32m.contains(&chunk_index) {
+            Some(maskblock * 16)
+        } else {
+            None
+        }
+    }
+}
+
+/// The smallest range holding all of `chunks`
+fn span(chunks: &[usize]) -> Range<usize> {
+    match (chunks.iter().min(), chunks.iter().max()) {
+        (Some(&first), Some(&last)) => first..last + 1,
+        _ => 0..0,
+    }
+}
+
+/// Decimal, C style `0x` hex or assembler style `h` suffixed hex
+fn parse_number(value: &str) -> Option<usize> {
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .or_else(|| value.strip_suffix(['h', 'H']))
+    {
+        usize::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 13] = [
+        "FONT", "FONTM", "PICS", "PICM", "SPRITES", "TILE8", "TILE8M", "TILE16", "TILE16M",
+        "TILE32", "TILE32M", "EXTERNS", "CHUNKS",
+    ];
+
+    fn counts(layout: &GrLayout) -> [usize; 13] {
+        [
+            layout.fonts.len(),
+            layout.fontm.len(),
+            layout.pics.len(),
+            layout.picm.len(),
+            layout.sprites.len(),
+            layout.num_tile8,
+            layout.num_tile8m,
+            layout.tile16.len(),
+            layout.tile16m.len(),
+            layout.tile32.len(),
+            layout.tile32m.len(),
+            layout.externs.len(),
+            layout.num_chunks,
+        ]
+    }
+
+    fn starts(layout: &GrLayout) -> [usize; 12] {
+        [
+            layout.fonts.start,
+            layout.fontm.start,
+            layout.pics.start,
+            layout.picm.start,
+            layout.sprites.start,
+            layout.tile8.start,
+            layout.tile8m.start,
+            layout.tile16.start,
+            layout.tile16m.start,
+            layout.tile32.start,
+            layout.tile32m.start,
+            layout.externs.start,
+        ]
+    }
+
+    #[test]
+    fn test_parse_h_and_equ() {
+        let layout = GrLayout::SOD;
+
+        let mut h = "typedef enum {\n    C_BACKDROPPIC=3,\n    TILE8=150,\n".to_string();
+        h += "    ORDERSCREEN=151,\n    ERRORSCREEN, // 152\n";
+        h += "    T_DEMO0=164,\n    T_DEMO1, T_DEMO2,\n    T_DEMO3, // 167\n";
+        h += "    T_ENDART1,\n    ENUMEND\n} graphicnums;\n\n";
+        let mut equ = "STRUCTPIC\t=\t0 ; pic sizes\n\n".to_string();
+        for (name, count) in NAMES.iter().zip(counts(&layout)) {
+            h += &format!("#define NUM{:<10} {} // amount\n", name, count);
+            equ += &format!("NUM{}\t=\t{:X}h\n", name, count);
+        }
+        for (name, start) in NAMES.iter().zip(starts(&layout)) {
+            h += &format!("#define START{:<10} {}\n", name, start);
+            equ += &format!("START{}\t=\t{}\n", name, start);
+        }
+
+        // Only the C header lists the demos, and this one an end art text too
+        assert_eq!(
+            GrLayout::parse(&h),
+            Ok(GrLayout {
+                articles: 168..169,
+                ..layout.clone()
+            })
+        );
+        assert_eq!(
+            GrLayout::parse(&equ),
+            Ok(GrLayout {
+                demos: 0..0,
+                articles: 0..0,
+                ..layout
+            })
+        );
+        assert_eq!(
+            GrLayout::parse("#define NUMCHUNKS 149"),
+            Err(GrLayoutError::MissingDefine("STARTFONT".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_built_in_layouts() {
+        for layout in [GrLayout::WL6, GrLayout::SOD, GrLayout::SDM] {
+            // Every kind follows the previous one, from after the pic table to the last extern
+            let mut next = 1;
+            for range in [
+                &layout.fonts,
+                &layout.fontm,
+                &layout.pics,
+                &layout.picm,
+                &layout.sprites,
+                &layout.tile8,
+                &layout.tile8m,
+                &layout.tile16,
+                &layout.tile16m,
+                &layout.tile32,
+                &layout.tile32m,
+                &layout.externs,
+            ] {
+                assert_eq!(range.start, next, "{:?}", layout);
+                next = range.end;
+            }
+            assert_eq!(next, layout.num_chunks);
+            assert!(layout.externs.start <= layout.demos.start);
+            assert!(layout.demos.end <= layout.externs.end);
+            assert!(layout.externs.start <= layout.articles.start);
+            assert!(layout.articles.end <= layout.externs.end);
+        }
+
+        assert_eq!(counts(&GrLayout::WL6)[..3], [2, 0, 132]);
+        assert_eq!(counts(&GrLayout::SOD)[..3], [2, 0, 147]);
+        assert_eq!(counts(&GrLayout::SDM)[..3], [2, 0, 127]);
+    }
+}
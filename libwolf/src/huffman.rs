@@ -0,0 +1,244 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io::{Read, Write},
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HuffError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Huffman node {node} refers to {value}, past the last node")]
+    InvalidNode { node: usize, value: u16 },
+    #[error("Huffman node {0} is reached more than once, the dictionary is not a tree")]
+    NotATree(usize),
+}
+
+/// A node of the tree stored in VGADICT. Values up to 255 are the decoded
+/// byte, higher values refer to node `value - 256`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HuffNode {
+    pub bit0: u16,
+    pub bit1: u16,
+}
+
+/// The 255 nodes of a tree covering every byte value, with the root last
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HuffDict {
+    pub nodes: [HuffNode; 255],
+}
+
+const ROOT: usize = 254;
+
+impl HuffDict {
+    /// Reads a dictionary and checks that it is a tree, so expanding can't
+    /// run off the nodes or loop forever
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, HuffError> {
+        let mut nodes = [HuffNode::default(); 255];
+        for node in nodes.iter_mut() {
+            node.bit0 = reader.read_u16::<LittleEndian>()?;
+            node.bit1 = reader.read_u16::<LittleEndian>()?;
+        }
+
+        let dict = Self { nodes };
+        dict.validate()?;
+        Ok(dict)
+    }
+
+    /// Checks that every node reachable from the root refers to bytes or to
+    /// existing nodes, and that none is reached twice
+    pub fn validate(&self) -> Result<(), HuffError> {
+        let mut visited = [false; 255];
+        let mut pending = vec![ROOT];
+
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut visited[index], true) {
+                return Err(HuffError::NotATree(index));
+            }
+
+            let node = self.nodes[index];
+            for value in [node.bit0, node.bit1] {
+                if value > 255 {
+                    pending.push(self.child(index, value)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The index of the node `value` refers to
+    fn child(&self, node: usize, value: u16) -> Result<usize, HuffError> {
+        let index = value as usize - 256;
+        if index < self.nodes.len() {
+            Ok(index)
+        } else {
+            Err(HuffError::InvalidNode { node, value })
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        for node in &self.nodes {
+            writer.write_u16::<LittleEndian>(node.bit0)?;
+            writer.write_u16::<LittleEndian>(node.bit1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the tree by repeatedly joining the two rarest symbols, as IGRAB
+    /// does. Bytes that never occur still get a code.
+    pub fn from_frequencies(frequencies: &[u64; 256]) -> Self {
+        // Ties are broken by the order nodes were added in, keeping it deterministic
+        let mut heap: BinaryHeap<_> = frequencies
+            .iter()
+            .zip(0u16..)
+            .map(|(&frequency, value)| Reverse((frequency, value)))
+            .collect();
+
+        let mut nodes = [HuffNode::default(); 255];
+        for (node, value) in nodes.iter_mut().zip(256u16..) {
+            let Reverse((frequency0, bit0)) = heap.pop().unwrap();
+            let Reverse((frequency1, bit1)) = heap.pop().unwrap();
+            *node = HuffNode { bit0, bit1 };
+            heap.push(Reverse((frequency0 + frequency1, value)));
+        }
+
+        Self { nodes }
+    }
+
+    /// Counts the bytes of `chunks` and builds a tree for them
+    pub fn for_data<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut frequencies = [0; 256];
+        for &byte in chunks.into_iter().flatten() {
+            frequencies[byte as usize] += 1;
+        }
+
+        Self::from_frequencies(&frequencies)
+    }
+
+    /// Decodes `length` bytes, ignoring the padding in the last byte
+    pub fn expand(&self, compressed: &[u8], length: usize) -> Result<Vec<u8>, HuffError> {
+        // Every bit yields at most one byte, a bogus length mustn't exhaust memory
+        let mut dest = Vec::with_capacity(length.min(compressed.len() * 8));
+        let mut index = ROOT;
+
+        'bytes: for &byte in compressed {
+            for bit in 0..8 {
+                if dest.len() == length {
+                    break 'bytes;
+                }
+
+                let node = self.nodes[index];
+                let value = if byte & (1 << bit) != 0 {
+                    node.bit1
+                } else {
+                    node.bit0
+                };
+
+                if value <= 255 {
+                    dest.push(value as u8);
+                    index = ROOT;
+                } else {
+                    index = self.child(index, value)?;
+                }
+            }
+        }
+
+        Ok(dest)
+    }
+
+    /// Encodes `data`, packing the bits of each code from the lowest bit up
+    pub fn compress(&self, data: &[u8]) -> Vec<u8> {
+        let codes = self.codes();
+        let mut compressed = Vec::new();
+        let mut current = 0u8;
+        let mut bit = 0;
+
+        for code in data.iter().map(|&byte| &codes[byte as usize]) {
+            for &set in code {
+                current |= (set as u8) << bit;
+                bit += 1;
+                if bit == 8 {
+                    compressed.push(current);
+                    current = 0;
+                    bit = 0;
+                }
+            }
+        }
+        if bit > 0 {
+            compressed.push(current);
+        }
+
+        compressed
+    }
+
+    /// The path from the root to every byte, `true` meaning `bit1`. Nodes
+    /// that [`HuffDict::validate`] would reject are left out.
+    fn codes(&self) -> Vec<Vec<bool>> {
+        let mut codes = vec![Vec::new(); 256];
+        let mut visited = [false; 255];
+        let mut pending = vec![(ROOT, Vec::new())];
+
+        while let Some((index, path)) = pending.pop() {
+            if std::mem::replace(&mut visited[index], true) {
+                continue;
+            }
+
+            let node = self.nodes[index];
+            for (value, set) in [(node.bit0, false), (node.bit1, true)] {
+                let mut path = path.clone();
+                path.push(set);
+                if value <= 255 {
+                    codes[value as usize] = path;
+                } else if let Ok(child) = self.child(index, value) {
+                    pending.push((child, path));
+                }
+            }
+        }
+
+        codes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data = b"Wolfenstein 3-D, Wolfenstein 3-D, Spear of Destiny".to_vec();
+        let dict = HuffDict::for_data([data.as_slice()]);
+
+        let mut written = Vec::new();
+        dict.write(&mut written).unwrap();
+        assert_eq!(written.len(), 255 * 4);
+        assert_eq!(HuffDict::read(written.as_slice()).unwrap(), dict);
+
+        let compressed = dict.compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(dict.expand(&compressed, data.len()).unwrap(), data);
+
+        // Bytes that were never counted can still be encoded
+        let unseen = [0, 255, 128];
+        assert_eq!(dict.expand(&dict.compress(&unseen), 3).unwrap(), unseen);
+
+        // The root referring past the last node, or back to itself
+        written[254 * 4..254 * 4 + 2].copy_from_slice(&511u16.to_le_bytes());
+        assert!(matches!(
+            HuffDict::read(written.as_slice()),
+            Err(HuffError::InvalidNode {
+                node: 254,
+                value: 511
+            })
+        ));
+        written[254 * 4..254 * 4 + 2].copy_from_slice(&510u16.to_le_bytes());
+        assert!(matches!(
+            HuffDict::read(written.as_slice()),
+            Err(HuffError::NotATree(254))
+        ));
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::Read;
+
+use thiserror::Error;
+
+use crate::{datasource::DataSource, variant::GameVariant};
+
+/// Retail releases of the data files, which differ in graphics layout and map fixes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Release {
+    V1_0,
+    V1_1,
+    /// 1.4 as published by Apogee
+    V1_4Apogee,
+    /// 1.4 as republished by GT Interactive
+    V1_4GT,
+    /// 1.4 as republished by Activision, the one sold digitally today
+    V1_4Activision,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileChecksum {
+    pub name: String,
+    pub size: u64,
+    pub crc32: u32,
+}
+
+pub struct KnownFile {
+    pub name: &'static str,
+    pub size: u64,
+    pub crc32: u32,
+    pub variant: GameVariant,
+    pub release: Release,
+    /// Whether the built-in graphics layout of `variant` matches this release
+    pub supported: bool,
+}
+
+/// Data files of retail releases. Only checksums taken from verified copies
+/// belong here, a wrong entry would misidentify every copy that matches it.
+pub const KNOWN_FILES: &[KnownFile] = &[];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Identification {
+    pub variant: GameVariant,
+    /// `None` for copies that are not in the table of known files
+    pub release: Option<Release>,
+    pub checksums: Vec<FileChecksum>,
+}
+
+#[derive(Debug, Error)]
+pub enum IdentifyError {
+    #[error("No Wolf3D or Spear of Destiny data found")]
+    NoGameData,
+    #[error("Could not read {name}: {source}")]
+    Io {
+        name: String,
+        source: std::io::Error,
+    },
+    #[error("{variant:?} release {release:?} is recognised but not supported")]
+    UnsupportedRelease {
+        variant: GameVariant,
+        release: Release,
+    },
+    #[error("{name} lists {chunks} chunks but {variant:?} has {expected}")]
+    UnknownLayout {
+        name: String,
+        variant: GameVariant,
+        chunks: usize,
+        expected: usize,
+    },
+}
+
+pub fn checksum(source: &dyn DataSource, name: &str) -> std::io::Result<FileChecksum> {
+    let mut reader = source.open(name)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut size = 0;
+    let mut buffer = [0; 8192];
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok(FileChecksum {
+        name: name.to_string(),
+        size,
+        crc32: hasher.finalize(),
+    })
+}
+
+/// Checksums the data files in `source` and looks them up in [`KNOWN_FILES`]
+pub fn identify(source: &dyn DataSource) -> Result<Identification, IdentifyError> {
+    identify_with(source, KNOWN_FILES)
+}
+
+pub fn identify_with(
+    source: &dyn DataSource,
+    known_files: &[KnownFile],
+) -> Result<Identification, IdentifyError> {
+    let variant =
+        GameVariant::detect_with(|name| source.exists(name)).ok_or(IdentifyError::NoGameData)?;
+
+    let checksums = variant
+        .data_files()
+        .into_iter()
+        .filter(|name| source.exists(name))
+        .map(|name| checksum(source, &name).map_err(|source| IdentifyError::Io { name, source }))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let known = checksums.iter().find_map(|checksum| {
+        known_files.iter().find(|known| {
+            known.variant == variant
+                && known.name.eq_ignore_ascii_case(&checksum.name)
+                && known.size == checksum.size
+                && known.crc32 == checksum.crc32
+        })
+    });
+
+    if let Some(known) = known.filter(|known| !known.supported) {
+        return Err(IdentifyError::UnsupportedRelease {
+            variant,
+            release: known.release,
+        });
+    }
+
+    // Copies missing from the table still have to fit the built-in layout,
+    // VGAHEAD holds one 3 byte offset per chunk plus the end of VGAGRAPH
+    let head_name = format!("VGAHEAD.{}", variant.graphics_extension());
+    if let Some(head) = checksums.iter().find(|checksum| checksum.name == head_name) {
+        let chunks = (head.size / 3).saturating_sub(1) as usize;
+        let expected = variant.gr_layout().num_chunks;
+        if chunks != expected {
+            return Err(IdentifyError::UnknownLayout {
+                name: head_name,
+                variant,
+                chunks,
+                expected,
+            });
+        }
+    }
+
+    Ok(Identification {
+        variant,
+        release: known.map(|known| known.release),
+        checksums,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::MemorySource;
+
+    #[test]
+    fn test_identify() {
+        let mut source = MemorySource::new();
+        source.insert("VSWAP.WL6", b"vswap".to_vec());
+        source.insert("VGAHEAD.WL6", vec![0; 150 * 3]);
+
+        let vswap = checksum(&source, "VSWAP.WL6").unwrap();
+        assert_eq!(vswap.size, 5);
+        assert_eq!(vswap.crc32, crc32fast::hash(b"vswap"));
+
+        let known = |supported| KnownFile {
+            name: "VSWAP.WL6",
+            size: vswap.size,
+            crc32: vswap.crc32,
+            variant: GameVariant::Wolf3d,
+            release: Release::V1_1,
+            supported,
+        };
+
+        let identification = identify(&source).unwrap();
+        assert_eq!(identification.variant, GameVariant::Wolf3d);
+        assert_eq!(identification.release, None);
+        assert_eq!(identification.checksums.len(), 2);
+
+        let identification = identify_with(&source, &[known(true)]).unwrap();
+        assert_eq!(identification.release, Some(Release::V1_1));
+
+        assert!(matches!(
+            identify_with(&source, &[known(false)]),
+            Err(IdentifyError::UnsupportedRelease {
+                release: Release::V1_1,
+                ..
+            })
+        ));
+
+        source.insert("VGAHEAD.WL6", vec![0; 140 * 3]);
+        assert!(matches!(
+            identify(&source),
+            Err(IdentifyError::UnknownLayout {
+                chunks: 139,
+                expected: 149,
+                ..
+            })
+        ));
+    }
+}
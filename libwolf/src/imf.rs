@@ -1,9 +1,10 @@
 use std::io::Cursor;
 
-use crate::audiot::{read_audiohed, read_audiot_chunk};
+use crate::audiot::AudioArchive;
+use crate::datasource::{DataSource, DirSource};
+use crate::variant::GameVariant;
 use byteorder::{LittleEndian, ReadBytesExt};
 
-const STARTMUSIC: usize = 261;
 const SONG_FREQ_HZ: u32 = 700;
 
 pub struct Imf {
@@ -19,21 +20,33 @@ pub struct Imf {
 impl Imf {
     pub fn new(
         wolf3d_path: &str,
+        variant: GameVariant,
         music_number: usize,
         output_sample_rate: u32,
     ) -> std::io::Result<Self> {
-        let opl = opl3_rs::Opl3Device::new(output_sample_rate);
+        Self::from_source(
+            &DirSource::new(wolf3d_path),
+            variant,
+            music_number,
+            output_sample_rate,
+        )
+    }
+
+    pub fn from_source(
+        source: &dyn DataSource,
+        variant: GameVariant,
+        music_number: usize,
+        output_sample_rate: u32,
+    ) -> std::io::Result<Self> {
+        let audio_data = AudioArchive::from_source(source, variant)?
+            .chunk(variant.music_start() + music_number)?;
 
-        let audio_head = read_audiohed(&mut std::fs::File::open(format!(
-            "{}/AUDIOHED.WL6",
-            wolf3d_path
-        ))?)?;
+        Self::from_chunk(audio_data, output_sample_rate)
+    }
 
-        let audio_data = read_audiot_chunk(
-            &mut std::fs::File::open(format!("{}/AUDIOT.WL6", wolf3d_path))?,
-            STARTMUSIC + music_number,
-            &audio_head,
-        )?;
+    /// Plays an already loaded music chunk of AUDIOT
+    pub fn from_chunk(audio_data: Vec<u8>, output_sample_rate: u32) -> std::io::Result<Self> {
+        let opl = opl3_rs::Opl3Device::new(output_sample_rate);
 
         let mut audio_cursor = Cursor::new(audio_data);
         let audio_len = audio_cursor.read_u16::<LittleEndian>()? as usize;
@@ -1,8 +1,28 @@
 pub mod audiot;
+pub mod article;
+pub mod automap;
+pub mod chunkcache;
+pub mod datasource;
+pub mod demo;
+pub mod font;
+pub mod gamedata;
 pub mod gamemaps;
 pub mod gr;
+pub mod grlayout;
+pub mod huffman;
+pub mod identify;
 pub mod imf;
+pub mod lint;
+pub mod mapjson;
+pub mod objects;
+pub mod palette;
+pub mod reachability;
 pub mod signon;
+pub mod stats;
+pub mod surface;
+pub mod tiled;
+pub mod tiles;
+pub mod variant;
 pub mod vswap;
 pub mod wl6_igrab;
 
@@ -0,0 +1,406 @@
+use std::fmt;
+
+use crate::{
+    gamemaps::Gamemaps,
+    objects::{Difficulty, ObjectSpawn, Spawn},
+    tiles::{DoorOrientation, Tile, AREATILE, NUMAREAS},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The original engine only handles 64x64 maps
+    WrongSize {
+        width: u16,
+        height: u16,
+    },
+    MissingPlayerStart,
+    DuplicatePlayerStart {
+        x: usize,
+        y: usize,
+    },
+    UnknownTile {
+        x: usize,
+        y: usize,
+        code: u16,
+    },
+    UnknownObject {
+        x: usize,
+        y: usize,
+        code: u16,
+    },
+    /// A door needs walls on the sides it doesn't open to
+    DoorNotFramed {
+        x: usize,
+        y: usize,
+    },
+    /// The tiles a door opens to need area codes, the door connects those areas
+    DoorWithoutArea {
+        x: usize,
+        y: usize,
+    },
+    /// A door opens to a tile past the last of the engine's [`NUMAREAS`] area codes
+    DoorAreaOutOfRange {
+        x: usize,
+        y: usize,
+        code: u16,
+    },
+    /// Both sides of a door are in the same area, so closing it separates nothing
+    DoorJoinsSameArea {
+        x: usize,
+        y: usize,
+        area: u16,
+    },
+    PushwallNotOnWall {
+        x: usize,
+        y: usize,
+    },
+    /// There is no side the pushwall can be pushed from into free space
+    PushwallBlocked {
+        x: usize,
+        y: usize,
+    },
+    ObjectInWall {
+        x: usize,
+        y: usize,
+        object: ObjectSpawn,
+    },
+}
+
+impl Diagnostic {
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            Diagnostic::WrongSize { .. } | Diagnostic::MissingPlayerStart => None,
+            Diagnostic::DuplicatePlayerStart { x, y }
+            | Diagnostic::UnknownTile { x, y, .. }
+            | Diagnostic::UnknownObject { x, y, .. }
+            | Diagnostic::DoorNotFramed { x, y }
+            | Diagnostic::DoorWithoutArea { x, y }
+            | Diagnostic::DoorAreaOutOfRange { x, y, .. }
+            | Diagnostic::DoorJoinsSameArea { x, y, .. }
+            | Diagnostic::PushwallNotOnWall { x, y }
+            | Diagnostic::PushwallBlocked { x, y }
+            | Diagnostic::ObjectInWall { x, y, .. } => Some((x, y)),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::WrongSize { width, height } => {
+                write!(f, "Map is {}x{}, expected 64x64", width, height)
+            }
+            Diagnostic::MissingPlayerStart => write!(f, "Map has no player start"),
+            Diagnostic::DuplicatePlayerStart { x, y } => {
+                write!(f, "Additional player start at {},{}", x, y)
+            }
+            Diagnostic::UnknownTile { x, y, code } => {
+                write!(f, "Unknown tile {} at {},{}", code, x, y)
+            }
+            Diagnostic::UnknownObject { x, y, code } => {
+                write!(f, "Unknown object {} at {},{}", code, x, y)
+            }
+            Diagnostic::DoorNotFramed { x, y } => {
+                write!(f, "Door at {},{} is not framed by walls", x, y)
+            }
+            Diagnostic::DoorWithoutArea { x, y } => {
+                write!(f, "Door at {},{} does not open to floor areas", x, y)
+            }
+            Diagnostic::DoorAreaOutOfRange { x, y, code } => write!(
+                f,
+                "Door at {},{} opens to tile {}, past the last area code",
+                x, y, code
+            ),
+            Diagnostic::DoorJoinsSameArea { x, y, area } => {
+                write!(f, "Door at {},{} has area {} on both sides", x, y, area)
+            }
+            Diagnostic::PushwallNotOnWall { x, y } => {
+                write!(f, "Pushwall at {},{} is not on a wall", x, y)
+            }
+            Diagnostic::PushwallBlocked { x, y } => {
+                write!(f, "Pushwall at {},{} has nowhere to move", x, y)
+            }
+            Diagnostic::ObjectInWall { x, y, object } => {
+                write!(f, "{:?} at {},{} is inside a wall", object, x, y)
+            }
+        }
+    }
+}
+
+/// Checks a map for mistakes that make the original engine misbehave or crash
+pub fn lint_map(map: &Gamemaps) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if map.width != 64 || map.height != 64 {
+        diagnostics.push(Diagnostic::WrongSize {
+            width: map.width,
+            height: map.height,
+        });
+    }
+
+    let tile_at = |x: usize, y: usize, dx: i32, dy: i32| {
+        let x = usize::try_from(x as i32 + dx).ok()?;
+        let y = usize::try_from(y as i32 + dy).ok()?;
+        map.tile(x, y)
+    };
+    let blocked_by_object = |x: usize, y: usize, dx: i32, dy: i32| {
+        let x = (x as i32 + dx) as usize;
+        let y = (y as i32 + dy) as usize;
+        matches!(map.object(x, y), Some(ObjectSpawn::Static(kind)) if kind.is_blocking())
+    };
+
+    for y in 0..map.height as usize {
+        for x in 0..map.width as usize {
+            match map.tile(x, y) {
+                Some(Tile::Unknown(code)) => {
+                    diagnostics.push(Diagnostic::UnknownTile { x, y, code })
+                }
+                Some(Tile::Door { orientation, .. }) => {
+                    let (side, passage) = match orientation {
+                        DoorOrientation::Vertical => ((0, 1), (1, 0)),
+                        DoorOrientation::Horizontal => ((1, 0), (0, 1)),
+                    };
+
+                    let framed = [1, -1].into_iter().all(|sign| {
+                        matches!(
+                            tile_at(x, y, side.0 * sign, side.1 * sign),
+                            Some(Tile::Wall(_))
+                        )
+                    });
+                    if !framed {
+                        diagnostics.push(Diagnostic::DoorNotFramed { x, y });
+                    }
+
+                    let sides =
+                        [1, -1].map(|sign| tile_at(x, y, passage.0 * sign, passage.1 * sign));
+
+                    // Ambush tiles take the area of their neighbours when the level is set up
+                    let out_of_range = sides.iter().find_map(|side| match *side {
+                        Some(Tile::Unknown(code)) if code >= AREATILE + NUMAREAS => Some(code),
+                        _ => None,
+                    });
+                    let has_areas = sides
+                        .iter()
+                        .all(|side| matches!(side, Some(Tile::Floor { .. } | Tile::AmbushFloor)));
+
+                    if let Some(code) = out_of_range {
+                        diagnostics.push(Diagnostic::DoorAreaOutOfRange { x, y, code });
+                    } else if !has_areas {
+                        diagnostics.push(Diagnostic::DoorWithoutArea { x, y });
+                    } else if let [Some(Tile::Floor { area }), Some(Tile::Floor { area: other })] =
+                        sides
+                    {
+                        if area == other {
+                            diagnostics.push(Diagnostic::DoorJoinsSameArea { x, y, area });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut player_starts = 0;
+
+    for Spawn { x, y, object } in map.spawns(Difficulty::Hard) {
+        let in_wall = map.tile(x, y).is_some_and(|tile| tile.is_solid());
+
+        match object {
+            ObjectSpawn::Unknown(code) => {
+                diagnostics.push(Diagnostic::UnknownObject { x, y, code });
+                continue;
+            }
+            ObjectSpawn::Pushwall => {
+                if !matches!(map.tile(x, y), Some(Tile::Wall(_))) {
+                    diagnostics.push(Diagnostic::PushwallNotOnWall { x, y });
+                    continue;
+                }
+
+                // Pushed from one side, the tile on the opposite side has to be free
+                let movable = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                    .into_iter()
+                    .any(|(dx, dy)| {
+                        let from = tile_at(x, y, -dx, -dy).is_some_and(|tile| !tile.is_solid());
+                        let to = tile_at(x, y, dx, dy).is_some_and(|tile| !tile.is_solid())
+                            && !blocked_by_object(x, y, dx, dy);
+                        from && to
+                    });
+                if !movable {
+                    diagnostics.push(Diagnostic::PushwallBlocked { x, y });
+                }
+                continue;
+            }
+            ObjectSpawn::PlayerStart(_) => {
+                player_starts += 1;
+                if player_starts > 1 {
+                    diagnostics.push(Diagnostic::DuplicatePlayerStart { x, y });
+                }
+            }
+            _ => {}
+        }
+
+        if in_wall {
+            diagnostics.push(Diagnostic::ObjectInWall { x, y, object });
+        }
+    }
+
+    if player_starts == 0 {
+        diagnostics.push(Diagnostic::MissingPlayerStart);
+    }
+
+    diagnostics
+}
+
+impl Gamemaps {
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        lint_map(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_from_codes(width: u16, plane0: &[u16], plane1: &[u16]) -> Gamemaps {
+        let to_bytes = |plane: &[u16]| {
+            plane
+                .iter()
+                .flat_map(|code| code.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+
+        Gamemaps {
+            index: 0,
+            plane0: to_bytes(plane0),
+            plane1: to_bytes(plane1),
+            plane2: vec![0; plane0.len() * 2],
+            width,
+            height: (plane0.len() / width as usize) as u16,
+            name: "Test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lint_map() {
+        #[rustfmt::skip]
+        let map = map_from_codes(
+            5,
+            &[
+                1,   1,   1,   1, 1,
+                1, 107,  90, 106, 1,
+                1, 107,   1,   1, 1,
+                1,  91, 107, 200, 1,
+                1,   1,   1,   1, 1,
+            ],
+            &[
+                0,  0, 0,  0, 0,
+                0, 19, 0,  0, 0,
+                0, 20, 52, 0, 0,
+                0,  0, 0,  0, 98,
+                0,  0, 0, 300, 0,
+            ],
+        );
+
+        assert_eq!(
+            map.lint(),
+            [
+                Diagnostic::WrongSize {
+                    width: 5,
+                    height: 5
+                },
+                Diagnostic::DoorNotFramed { x: 1, y: 3 },
+                Diagnostic::DoorWithoutArea { x: 1, y: 3 },
+                Diagnostic::UnknownTile {
+                    x: 3,
+                    y: 3,
+                    code: 200
+                },
+                Diagnostic::DuplicatePlayerStart { x: 1, y: 2 },
+                Diagnostic::ObjectInWall {
+                    x: 2,
+                    y: 2,
+                    object: ObjectSpawn::Static(crate::objects::StaticKind::Cross)
+                },
+                Diagnostic::PushwallBlocked { x: 4, y: 3 },
+                Diagnostic::UnknownObject {
+                    x: 3,
+                    y: 4,
+                    code: 300
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_clean_map() {
+        #[rustfmt::skip]
+        let map = map_from_codes(
+            5,
+            &[
+                1,   1,   1,   1, 1,
+                1, 107,  90, 108, 1,
+                1,   1,   1,   1, 1,
+            ],
+            &[
+                0,  0, 0, 0, 0,
+                0, 19, 0, 0, 0,
+                0,  0, 0, 0, 0,
+            ],
+        );
+
+        assert_eq!(
+            map.lint(),
+            [Diagnostic::WrongSize {
+                width: 5,
+                height: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_door_areas() {
+        #[rustfmt::skip]
+        let map = map_from_codes(
+            5,
+            &[
+                1,   1,  1,   1, 1,
+                1, 107, 90, 107, 1,
+                1,   1,  1,   1, 1,
+                1, 107, 90, 150, 1,
+                1,   1,  1,   1, 1,
+            ],
+            &[
+                0,  0, 0, 0, 0,
+                0, 19, 0, 0, 0,
+                0,  0, 0, 0, 0,
+                0,  0, 0, 0, 0,
+                0,  0, 0, 0, 0,
+            ],
+        );
+
+        assert_eq!(
+            map.lint(),
+            [
+                Diagnostic::WrongSize {
+                    width: 5,
+                    height: 5
+                },
+                Diagnostic::DoorJoinsSameArea {
+                    x: 2,
+                    y: 1,
+                    area: 0
+                },
+                Diagnostic::DoorAreaOutOfRange {
+                    x: 2,
+                    y: 3,
+                    code: 150
+                },
+                Diagnostic::UnknownTile {
+                    x: 3,
+                    y: 3,
+                    code: 150
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::gamemaps::Gamemaps;
+
+#[derive(Debug, Error)]
+pub enum MapJsonError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Plane {plane} does not have {height} rows of {width} words")]
+    PlaneSizeMismatch {
+        plane: usize,
+        width: u16,
+        height: u16,
+    },
+}
+
+#[derive(Deserialize)]
+struct MapJson {
+    index: usize,
+    name: String,
+    width: u16,
+    height: u16,
+    plane0: Vec<Vec<u16>>,
+    plane1: Vec<Vec<u16>>,
+    plane2: Vec<Vec<u16>>,
+}
+
+impl Gamemaps {
+    /// Lossless dump of all three planes, one map row per line so it diffs well
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n");
+        json += &format!("  \"index\": {},\n", self.index);
+        // A string always serializes
+        json += &format!(
+            "  \"name\": {},\n",
+            serde_json::to_string(&self.name).unwrap()
+        );
+        json += &format!("  \"width\": {},\n", self.width);
+        json += &format!("  \"height\": {},\n", self.height);
+
+        let planes = [&self.plane0, &self.plane1, &self.plane2];
+        for (i, plane) in planes.into_iter().enumerate() {
+            let words = plane
+                .chunks_exact(2)
+                .map(|word| u16::from_le_bytes([word[0], word[1]]).to_string())
+                .collect::<Vec<_>>();
+            let rows = words
+                .chunks(self.width.max(1) as usize)
+                .map(|row| format!("    [{}]", row.join(", ")))
+                .collect::<Vec<_>>();
+
+            json += &format!("  \"plane{}\": [\n{}\n  ]", i, rows.join(",\n"));
+            json += if i < 2 { ",\n" } else { "\n" };
+        }
+
+        json += "}\n";
+        json
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, MapJsonError> {
+        let map: MapJson = serde_json::from_str(json)?;
+
+        let planes = [map.plane0, map.plane1, map.plane2]
+            .into_iter()
+            .enumerate()
+            .map(|(plane, rows)| {
+                let valid = rows.len() == map.height as usize
+                    && rows.iter().all(|row| row.len() == map.width as usize);
+                if !valid {
+                    return Err(MapJsonError::PlaneSizeMismatch {
+                        plane,
+                        width: map.width,
+                        height: map.height,
+                    });
+                }
+
+                Ok(rows
+                    .iter()
+                    .flatten()
+                    .flat_map(|word| word.to_le_bytes())
+                    .collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let [plane0, plane1, plane2]: [Vec<u8>; 3] = planes.try_into().unwrap();
+
+        Ok(Self {
+            index: map.index,
+            plane0,
+            plane1,
+            plane2,
+            width: map.width,
+            height: map.height,
+            name: map.name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let map = Gamemaps {
+            index: 3,
+            plane0: (0..6u16).flat_map(|w| (w * 1000).to_le_bytes()).collect(),
+            plane1: (0..6u16).flat_map(|w| w.to_le_bytes()).collect(),
+            plane2: vec![0xFF; 12],
+            width: 3,
+            height: 2,
+            name: "\"Quoted\" name".to_string(),
+        };
+
+        let json = map.to_json();
+        assert!(json.contains("    [0, 1000, 2000],\n    [3000, 4000, 5000]\n"));
+
+        let loaded = Gamemaps::from_json(&json).unwrap();
+        assert_eq!(loaded.index, map.index);
+        assert_eq!(loaded.name, map.name);
+        assert_eq!((loaded.width, loaded.height), (map.width, map.height));
+        assert_eq!(loaded.plane0, map.plane0);
+        assert_eq!(loaded.plane1, map.plane1);
+        assert_eq!(loaded.plane2, map.plane2);
+
+        let json = json.replace("[3000, 4000, 5000]", "[3000, 4000]");
+        assert!(matches!(
+            Gamemaps::from_json(&json),
+            Err(MapJsonError::PlaneSizeMismatch { plane: 0, .. })
+        ));
+    }
+}
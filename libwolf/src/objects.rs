@@ -0,0 +1,522 @@
+use crate::gamemaps::Gamemaps;
+
+//
+// Object codes of plane 1, see ScanInfoPlane in WL_GAME.C
+//
+pub const PUSHABLETILE: u16 = 98;
+pub const EXITTILE: u16 = 99;
+pub const ICONARROWS: u16 = 90;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Baby,
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Baby,
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+    ];
+}
+
+/// Same order as `dirtype` in WL_DEF.H
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dir {
+    East,
+    NorthEast,
+    North,
+    NorthWest,
+    West,
+    SouthWest,
+    South,
+    SouthEast,
+}
+
+impl Dir {
+    const ALL: [Dir; 8] = [
+        Dir::East,
+        Dir::NorthEast,
+        Dir::North,
+        Dir::NorthWest,
+        Dir::West,
+        Dir::SouthWest,
+        Dir::South,
+        Dir::SouthEast,
+    ];
+
+    /// Unit step in map coordinates, y grows southwards
+    pub fn delta(&self) -> (i32, i32) {
+        match self {
+            Dir::East => (1, 0),
+            Dir::NorthEast => (1, -1),
+            Dir::North => (0, -1),
+            Dir::NorthWest => (-1, -1),
+            Dir::West => (-1, 0),
+            Dir::SouthWest => (-1, 1),
+            Dir::South => (0, 1),
+            Dir::SouthEast => (1, 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EnemyKind {
+    Guard,
+    Officer,
+    SS,
+    Dog,
+    Mutant,
+    HansGrosse,
+    Schabbs,
+    FakeHitler,
+    Hitler,
+    Gretel,
+    Giftmacher,
+    Fettgesicht,
+    Blinky,
+    Clyde,
+    Pinky,
+    Inky,
+}
+
+/// Statics in the order of `statinfo` in WL_ACT1.C, spawned from codes 23 onwards
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StaticKind {
+    Puddle,
+    GreenBarrel,
+    TableWithChairs,
+    FloorLamp,
+    Chandelier,
+    HangedMan,
+    DogFood,
+    RedPillar,
+    Tree,
+    SkeletonFlat,
+    Sink,
+    PottedPlant,
+    Urn,
+    BareTable,
+    CeilingLight,
+    KitchenStuff,
+    SuitOfArmor,
+    HangingCage,
+    SkeletonInCage,
+    SkeletonRelax,
+    GoldKey,
+    SilverKey,
+    Bed,
+    Basket,
+    Food,
+    FirstAid,
+    Clip,
+    MachineGun,
+    GatlingGun,
+    Cross,
+    Chalice,
+    Chest,
+    Crown,
+    OneUp,
+    Gibs,
+    Barrel,
+    Well,
+    EmptyWell,
+    Gibs2,
+    Flag,
+    CallApogee,
+    Junk1,
+    Junk2,
+    Junk3,
+    Pots,
+    Stove,
+    Spears,
+    Vines,
+}
+
+impl StaticKind {
+    const ALL: [StaticKind; 48] = [
+        StaticKind::Puddle,
+        StaticKind::GreenBarrel,
+        StaticKind::TableWithChairs,
+        StaticKind::FloorLamp,
+        StaticKind::Chandelier,
+        StaticKind::HangedMan,
+        StaticKind::DogFood,
+        StaticKind::RedPillar,
+        StaticKind::Tree,
+        StaticKind::SkeletonFlat,
+        StaticKind::Sink,
+        StaticKind::PottedPlant,
+        StaticKind::Urn,
+        StaticKind::BareTable,
+        StaticKind::CeilingLight,
+        StaticKind::KitchenStuff,
+        StaticKind::SuitOfArmor,
+        StaticKind::HangingCage,
+        StaticKind::SkeletonInCage,
+        StaticKind::SkeletonRelax,
+        StaticKind::GoldKey,
+        StaticKind::SilverKey,
+        StaticKind::Bed,
+        StaticKind::Basket,
+        StaticKind::Food,
+        StaticKind::FirstAid,
+        StaticKind::Clip,
+        StaticKind::MachineGun,
+        StaticKind::GatlingGun,
+        StaticKind::Cross,
+        StaticKind::Chalice,
+        StaticKind::Chest,
+        StaticKind::Crown,
+        StaticKind::OneUp,
+        StaticKind::Gibs,
+        StaticKind::Barrel,
+        StaticKind::Well,
+        StaticKind::EmptyWell,
+        StaticKind::Gibs2,
+        StaticKind::Flag,
+        StaticKind::CallApogee,
+        StaticKind::Junk1,
+        StaticKind::Junk2,
+        StaticKind::Junk3,
+        StaticKind::Pots,
+        StaticKind::Stove,
+        StaticKind::Spears,
+        StaticKind::Vines,
+    ];
+
+    /// Whether the player can pick it up, `bo_*` items in `statinfo`
+    pub fn is_bonus(&self) -> bool {
+        matches!(
+            self,
+            StaticKind::DogFood
+                | StaticKind::GoldKey
+                | StaticKind::SilverKey
+                | StaticKind::Food
+                | StaticKind::FirstAid
+                | StaticKind::Clip
+                | StaticKind::MachineGun
+                | StaticKind::GatlingGun
+                | StaticKind::Cross
+                | StaticKind::Chalice
+                | StaticKind::Chest
+                | StaticKind::Crown
+                | StaticKind::OneUp
+                | StaticKind::Gibs
+                | StaticKind::Gibs2
+        )
+    }
+
+    /// Score for picking it up, only set for the items counted in the treasure ratio
+    pub fn treasure_points(&self) -> Option<u32> {
+        match self {
+            StaticKind::Cross => Some(100),
+            StaticKind::Chalice => Some(500),
+            StaticKind::Chest => Some(1000),
+            StaticKind::Crown => Some(5000),
+            StaticKind::OneUp => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Health given when picked up, see GetBonus in WL_AGENT.C
+    pub fn health(&self) -> u32 {
+        match self {
+            StaticKind::DogFood => 4,
+            StaticKind::Food => 10,
+            StaticKind::FirstAid => 25,
+            StaticKind::Gibs | StaticKind::Gibs2 => 1,
+            StaticKind::OneUp => 99,
+            _ => 0,
+        }
+    }
+
+    /// Ammo given when picked up, see GetBonus in WL_AGENT.C
+    pub fn ammo(&self) -> u32 {
+        match self {
+            StaticKind::Clip => 8,
+            StaticKind::MachineGun | StaticKind::GatlingGun => 6,
+            StaticKind::OneUp => 25,
+            _ => 0,
+        }
+    }
+
+    /// Whether the player and enemies can't walk through it
+    pub fn is_blocking(&self) -> bool {
+        matches!(
+            self,
+            StaticKind::GreenBarrel
+                | StaticKind::TableWithChairs
+                | StaticKind::FloorLamp
+                | StaticKind::HangedMan
+                | StaticKind::RedPillar
+                | StaticKind::Tree
+                | StaticKind::Sink
+                | StaticKind::PottedPlant
+                | StaticKind::Urn
+                | StaticKind::BareTable
+                | StaticKind::SuitOfArmor
+                | StaticKind::HangingCage
+                | StaticKind::SkeletonInCage
+                | StaticKind::Bed
+                | StaticKind::Barrel
+                | StaticKind::Well
+                | StaticKind::EmptyWell
+                | StaticKind::Flag
+                | StaticKind::CallApogee
+                | StaticKind::Stove
+                | StaticKind::Spears
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectSpawn {
+    PlayerStart(Dir),
+    Static(StaticKind),
+    Enemy {
+        kind: EnemyKind,
+        /// Bosses and ghosts don't face anywhere until they see the player
+        direction: Option<Dir>,
+        patrolling: bool,
+        min_difficulty: Difficulty,
+    },
+    DeadGuard,
+    /// Marks the wall in plane 0 as a secret pushwall
+    Pushwall,
+    /// Patrolling enemies change direction when they reach one
+    TurnPoint(Dir),
+    /// Walking onto it wins the game
+    ExitTile,
+    Unknown(u16),
+}
+
+impl ObjectSpawn {
+    /// Decodes an object code, `None` for code 0 which means no object
+    pub fn from_code(code: u16) -> Option<Self> {
+        let spawn = match code {
+            0 => return None,
+            19..=22 => ObjectSpawn::PlayerStart(
+                [Dir::North, Dir::East, Dir::South, Dir::West][code as usize - 19],
+            ),
+            23..=70 => ObjectSpawn::Static(StaticKind::ALL[code as usize - 23]),
+            ICONARROWS..=97 => ObjectSpawn::TurnPoint(Dir::ALL[(code - ICONARROWS) as usize]),
+            PUSHABLETILE => ObjectSpawn::Pushwall,
+            EXITTILE => ObjectSpawn::ExitTile,
+            124 => ObjectSpawn::DeadGuard,
+            160 => Self::boss(EnemyKind::FakeHitler),
+            178 => Self::boss(EnemyKind::Hitler),
+            179 => Self::boss(EnemyKind::Fettgesicht),
+            196 => Self::boss(EnemyKind::Schabbs),
+            197 => Self::boss(EnemyKind::Gretel),
+            214 => Self::boss(EnemyKind::HansGrosse),
+            215 => Self::boss(EnemyKind::Giftmacher),
+            224 => Self::ghost(EnemyKind::Blinky),
+            225 => Self::ghost(EnemyKind::Clyde),
+            226 => Self::ghost(EnemyKind::Pinky),
+            227 => Self::ghost(EnemyKind::Inky),
+            216..=223 | 234..=241 | 252..=259 => {
+                // Mutant tiers are 18 codes apart instead of 36
+                let tier = (code - 216) / 18;
+                Self::soldier(EnemyKind::Mutant, code - 216 - tier * 18, tier)
+            }
+            108..=215 => {
+                let tier = (code - 108) / 36;
+                let offset = (code - 108) % 36;
+                let kind = match offset {
+                    0..=7 => EnemyKind::Guard,
+                    8..=15 => EnemyKind::Officer,
+                    18..=25 => EnemyKind::SS,
+                    26..=33 => EnemyKind::Dog,
+                    _ => return Some(ObjectSpawn::Unknown(code)),
+                };
+                // Officers sit right after guards, SS and dogs after the dead guard and a gap
+                let offset = if offset < 16 {
+                    offset % 8
+                } else {
+                    (offset - 18) % 8
+                };
+                Self::soldier(kind, offset, tier)
+            }
+            _ => ObjectSpawn::Unknown(code),
+        };
+
+        Some(spawn)
+    }
+
+    fn soldier(kind: EnemyKind, offset: u16, tier: u16) -> Self {
+        // Standing enemies face east, north, west or south, then the same for patrols
+        ObjectSpawn::Enemy {
+            kind,
+            direction: Some(Dir::ALL[(offset % 4) as usize * 2]),
+            patrolling: offset >= 4,
+            min_difficulty: [Difficulty::Baby, Difficulty::Medium, Difficulty::Hard][tier as usize],
+        }
+    }
+
+    fn boss(kind: EnemyKind) -> Self {
+        ObjectSpawn::Enemy {
+            kind,
+            direction: None,
+            patrolling: false,
+            min_difficulty: Difficulty::Baby,
+        }
+    }
+
+    fn ghost(kind: EnemyKind) -> Self {
+        ObjectSpawn::Enemy {
+            kind,
+            direction: Some(Dir::East),
+            patrolling: false,
+            min_difficulty: Difficulty::Baby,
+        }
+    }
+
+    /// Lowest difficulty the object appears on
+    pub fn min_difficulty(&self) -> Difficulty {
+        match self {
+            ObjectSpawn::Enemy { min_difficulty, .. } => *min_difficulty,
+            _ => Difficulty::Baby,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spawn {
+    pub x: usize,
+    pub y: usize,
+    pub object: ObjectSpawn,
+}
+
+impl Gamemaps {
+    pub fn object(&self, x: usize, y: usize) -> Option<ObjectSpawn> {
+        self.word(1, x, y).and_then(ObjectSpawn::from_code)
+    }
+
+    /// All objects in plane 1 that appear when playing on `difficulty`, in row order
+    pub fn spawns(&self, difficulty: Difficulty) -> impl Iterator<Item = Spawn> + '_ {
+        let width = self.width as usize;
+        (0..self.height as usize)
+            .flat_map(move |y| (0..width).map(move |x| (x, y)))
+            .filter_map(|(x, y)| self.object(x, y).map(|object| Spawn { x, y, object }))
+            .filter(move |spawn| spawn.object.min_difficulty() <= difficulty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enemy(
+        kind: EnemyKind,
+        direction: Dir,
+        patrolling: bool,
+        min_difficulty: Difficulty,
+    ) -> Option<ObjectSpawn> {
+        Some(ObjectSpawn::Enemy {
+            kind,
+            direction: Some(direction),
+            patrolling,
+            min_difficulty,
+        })
+    }
+
+    #[test]
+    fn test_decode_objects() {
+        assert_eq!(ObjectSpawn::from_code(0), None);
+        assert_eq!(
+            ObjectSpawn::from_code(19),
+            Some(ObjectSpawn::PlayerStart(Dir::North))
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(22),
+            Some(ObjectSpawn::PlayerStart(Dir::West))
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(23),
+            Some(ObjectSpawn::Static(StaticKind::Puddle))
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(52),
+            Some(ObjectSpawn::Static(StaticKind::Cross))
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(70),
+            Some(ObjectSpawn::Static(StaticKind::Vines))
+        );
+        assert_eq!(ObjectSpawn::from_code(71), Some(ObjectSpawn::Unknown(71)));
+        assert_eq!(
+            ObjectSpawn::from_code(92),
+            Some(ObjectSpawn::TurnPoint(Dir::North))
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(97),
+            Some(ObjectSpawn::TurnPoint(Dir::SouthEast))
+        );
+        assert_eq!(ObjectSpawn::from_code(98), Some(ObjectSpawn::Pushwall));
+        assert_eq!(ObjectSpawn::from_code(124), Some(ObjectSpawn::DeadGuard));
+
+        assert_eq!(
+            ObjectSpawn::from_code(108),
+            enemy(EnemyKind::Guard, Dir::East, false, Difficulty::Baby)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(113),
+            enemy(EnemyKind::Guard, Dir::North, true, Difficulty::Baby)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(146),
+            enemy(EnemyKind::Guard, Dir::West, false, Difficulty::Medium)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(191),
+            enemy(EnemyKind::Officer, Dir::South, false, Difficulty::Hard)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(130),
+            enemy(EnemyKind::SS, Dir::East, true, Difficulty::Baby)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(213),
+            enemy(EnemyKind::Dog, Dir::South, true, Difficulty::Hard)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(219),
+            enemy(EnemyKind::Mutant, Dir::South, false, Difficulty::Baby)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(238),
+            enemy(EnemyKind::Mutant, Dir::East, true, Difficulty::Medium)
+        );
+        assert_eq!(
+            ObjectSpawn::from_code(259),
+            enemy(EnemyKind::Mutant, Dir::South, true, Difficulty::Hard)
+        );
+
+        for (code, kind) in [
+            (160, EnemyKind::FakeHitler),
+            (178, EnemyKind::Hitler),
+            (179, EnemyKind::Fettgesicht),
+            (196, EnemyKind::Schabbs),
+            (197, EnemyKind::Gretel),
+            (214, EnemyKind::HansGrosse),
+            (215, EnemyKind::Giftmacher),
+        ] {
+            assert!(matches!(
+                ObjectSpawn::from_code(code),
+                Some(ObjectSpawn::Enemy { kind: k, direction: None, .. }) if k == kind
+            ));
+        }
+
+        for code in [125, 142, 143, 161, 228, 260] {
+            assert_eq!(
+                ObjectSpawn::from_code(code),
+                Some(ObjectSpawn::Unknown(code))
+            );
+        }
+    }
+}
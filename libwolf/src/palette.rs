@@ -0,0 +1,205 @@
+use thiserror::Error;
+
+use crate::variant::GameVariant;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaletteError {
+    #[error("A palette holds 768 bytes, not {0}")]
+    InvalidSize(usize),
+    #[error("Not a JASC palette: {0}")]
+    InvalidJasc(String),
+}
+
+pub const NUMREDSHIFTS: usize = 6;
+pub const REDSTEPS: i32 = 8;
+pub const NUMWHITESHIFTS: usize = 3;
+pub const WHITESTEPS: i32 = 20;
+
+/// Expands a 6 bit VGA DAC value to 8 bits, so that 63 becomes 255
+pub fn vga_to_rgb8(value: u8) -> u8 {
+    (value << 2) | (value >> 4)
+}
+
+/// 256 colours of 6 bit VGA DAC values, as the game sets them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [[u8; 3]; 256],
+}
+
+impl Palette {
+    /// The Wolfenstein 3D palette, [`crate::GAMEPAL`]
+    pub fn game() -> Self {
+        Self::from_vga(crate::GAMEPAL).unwrap()
+    }
+
+    /// The game palette of `variant`, see [`GameVariant::palette`]
+    pub fn for_variant(variant: GameVariant) -> Option<Self> {
+        variant.palette().map(|data| Self::from_vga(data).unwrap())
+    }
+
+    /// Reads 768 bytes of 6 bit values, like `GAMEPAL.BIN` or the palette
+    /// chunks of Spear of Destiny's VGAGRAPH
+    pub fn from_vga(data: &[u8]) -> Result<Self, PaletteError> {
+        if data.len() != 768 {
+            return Err(PaletteError::InvalidSize(data.len()));
+        }
+
+        let mut colors = [[0; 3]; 256];
+        for (color, rgb) in colors.iter_mut().zip(data.chunks_exact(3)) {
+            color.copy_from_slice(rgb);
+        }
+
+        Ok(Self { colors })
+    }
+
+    /// Reads a raw 768 byte `.pal` file. Files holding nothing above 63 are
+    /// taken as 6 bit values, anything else as 8 bit values.
+    pub fn from_pal(data: &[u8]) -> Result<Self, PaletteError> {
+        if data.iter().all(|&value| value < 64) {
+            Self::from_vga(data)
+        } else {
+            let data: Vec<u8> = data.iter().map(|&value| value >> 2).collect();
+            Self::from_vga(&data)
+        }
+    }
+
+    /// Reads a JASC-PAL file of 8 bit values, as written by Paint Shop Pro
+    pub fn from_jasc(text: &str) -> Result<Self, PaletteError> {
+        let invalid = |message: &str| PaletteError::InvalidJasc(message.to_string());
+        let mut lines = text.lines().map(str::trim);
+
+        if lines.next() != Some("JASC-PAL") || lines.next() != Some("0100") {
+            return Err(invalid("missing JASC-PAL header"));
+        }
+        if lines.next().and_then(|count| count.parse().ok()) != Some(256) {
+            return Err(invalid("only palettes of 256 colours are supported"));
+        }
+
+        let mut data = Vec::with_capacity(768);
+        for line in lines.filter(|line| !line.is_empty()).take(256) {
+            for value in line.split_whitespace() {
+                let value: u8 = value
+                    .parse()
+                    .map_err(|_| invalid(&format!("{} is not a colour value", value)))?;
+                data.push(value >> 2);
+            }
+        }
+
+        Self::from_vga(&data)
+    }
+
+    /// Writes a JASC-PAL file of 8 bit values
+    pub fn to_jasc(&self) -> String {
+        let mut text = "JASC-PAL\r\n0100\r\n256\r\n".to_string();
+        for color in self.to_rgb8() {
+            text += &format!("{} {} {}\r\n", color[0], color[1], color[2]);
+        }
+        text
+    }
+
+    pub fn to_rgb8(&self) -> [[u8; 3]; 256] {
+        self.colors.map(|color| color.map(vga_to_rgb8))
+    }
+
+    /// Packs each colour as `0xAARRGGBB` with full alpha, the layout of most
+    /// 32 bit framebuffers
+    pub fn to_u32(&self) -> [u32; 256] {
+        self.to_rgb8()
+            .map(|[r, g, b]| u32::from_be_bytes([0xFF, r, g, b]))
+    }
+
+    /// A palette of nothing but `color`, the end of a fade out
+    pub fn filled(color: [u8; 3]) -> Self {
+        Self {
+            colors: [color; 256],
+        }
+    }
+
+    /// Moves every colour `step / steps` of the way towards `target`,
+    /// rounding towards the original like `VL_FadeIn` and `VL_FadeOut`
+    pub fn lerp(&self, target: &Palette, step: i32, steps: i32) -> Self {
+        let mut colors = self.colors;
+        for (color, target) in colors.iter_mut().zip(&target.colors) {
+            for (value, &target) in color.iter_mut().zip(target) {
+                let delta = target as i32 - *value as i32;
+                *value = (*value as i32 + delta * step / steps) as u8;
+            }
+        }
+
+        Self { colors }
+    }
+
+    /// The palettes `VL_FadeIn`/`VL_FadeOut` set, one per step, followed by
+    /// `target` itself
+    pub fn fade(&self, target: &Palette, steps: i32) -> Vec<Self> {
+        (0..steps)
+            .map(|step| self.lerp(target, step, steps))
+            .chain(std::iter::once(target.clone()))
+            .collect()
+    }
+
+    /// Shifts towards `(red, green, blue)` by `step / steps` the way
+    /// `InitRedShifts` does, where red may reach 64
+    fn shift(&self, red: u8, green: u8, blue: u8, step: i32, steps: i32) -> Self {
+        self.lerp(&Self::filled([red, green, blue]), step, steps)
+    }
+
+    /// The palettes shown when the player takes damage, from faint to strong
+    pub fn red_shifts(&self) -> [Self; NUMREDSHIFTS] {
+        std::array::from_fn(|i| self.shift(64, 0, 0, i as i32 + 1, REDSTEPS))
+    }
+
+    /// The palettes shown when the player picks up a bonus item
+    pub fn white_shifts(&self) -> [Self; NUMWHITESHIFTS] {
+        std::array::from_fn(|i| self.shift(64, 62, 0, i as i32 + 1, WHITESTEPS))
+    }
+}
+
+impl AsRef<[u8]> for Palette {
+    /// The raw 6 bit values, as taken by [`crate::surface::Surface::to_rgba`]
+    fn as_ref(&self) -> &[u8] {
+        self.colors.as_flattened()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_and_shifts() {
+        let palette = Palette::game();
+        assert_eq!(palette.as_ref(), crate::GAMEPAL);
+        assert_eq!([0, 32, 63].map(vga_to_rgb8), [0, 130, 255]);
+
+        let mut colors = [[0; 3]; 256];
+        colors[1] = [63, 63, 63];
+        colors[2] = [10, 20, 30];
+        let palette = Palette { colors };
+        assert_eq!(palette.to_u32()[1], 0xFF_FF_FF_FF);
+        assert_eq!(palette.to_u32()[2], 0xFF_28_51_79);
+
+        // 10 + 54 * 6 / 8, 20 - 20 * 6 / 8, 30 - 30 * 6 / 8
+        assert_eq!(palette.red_shifts()[5].colors[2], [50, 5, 8]);
+        // 10 + 54 / 20, 20 + 42 / 20, 30 - 30 / 20
+        assert_eq!(palette.white_shifts()[0].colors[2], [12, 22, 29]);
+
+        let fade = palette.fade(&Palette::filled([0; 3]), 4);
+        assert_eq!(fade.len(), 5);
+        assert_eq!(fade[0], palette);
+        assert_eq!(fade[2].colors[2], [5, 10, 15]);
+        assert_eq!(fade[4].colors[2], [0, 0, 0]);
+
+        let jasc = palette.to_jasc();
+        assert!(jasc.starts_with("JASC-PAL\r\n0100\r\n256\r\n0 0 0\r\n255 255 255\r\n"));
+        assert_eq!(Palette::from_jasc(&jasc), Ok(palette.clone()));
+        assert_eq!(
+            Palette::from_pal(palette.to_rgb8().as_flattened()),
+            Ok(palette)
+        );
+        assert_eq!(
+            Palette::from_pal(&[0; 10]),
+            Err(PaletteError::InvalidSize(10))
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+
+use crate::{
+    gamemaps::Gamemaps,
+    objects::{Difficulty, ObjectSpawn, Spawn},
+    tiles::{Tile, ELEVATORTILE},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reach {
+    Unreachable,
+    Reachable,
+    /// Only reachable after moving one or more pushwalls
+    SecretOnly,
+}
+
+/// Flood fill from the player start, doors and keys are assumed to open
+///
+/// A pushwall is treated as floor as soon as the player can stand next to it, where
+/// it ends up after being pushed is not simulated.
+pub struct Reachability {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Reach>,
+    /// Bonus items and enemies the player can't get to at all
+    pub unreachable: Vec<Spawn>,
+    /// Bonus items and enemies behind pushwalls
+    pub secret_only: Vec<Spawn>,
+    /// Elevator switches the player can press, together with how they are reached
+    pub exits: Vec<(usize, usize, Reach)>,
+}
+
+impl Reachability {
+    /// `None` if the map has no player start
+    pub fn new(map: &Gamemaps, difficulty: Difficulty) -> Option<Self> {
+        let width = map.width as usize;
+        let height = map.height as usize;
+
+        let start = map
+            .spawns(difficulty)
+            .find(|spawn| matches!(spawn.object, ObjectSpawn::PlayerStart(_)))?;
+
+        let walkable = |x: usize, y: usize| {
+            let open_tile = map.tile(x, y).is_some_and(|tile| !tile.is_solid());
+            let blocked =
+                matches!(map.object(x, y), Some(ObjectSpawn::Static(kind)) if kind.is_blocking());
+            open_tile && !blocked
+        };
+        let pushwall = |x: usize, y: usize| matches!(map.object(x, y), Some(ObjectSpawn::Pushwall));
+
+        let mut cells = vec![Reach::Unreachable; width * height];
+        cells[start.y * width + start.x] = Reach::Reachable;
+
+        let mut queue = VecDeque::from([(start.x, start.y)]);
+        let mut pushwalls = Vec::new();
+        Self::flood(
+            &mut cells,
+            width,
+            height,
+            &mut queue,
+            Reach::Reachable,
+            |x, y| {
+                if walkable(x, y) {
+                    true
+                } else {
+                    if pushwall(x, y) {
+                        pushwalls.push((x, y));
+                    }
+                    false
+                }
+            },
+        );
+
+        // Everything found from here on is only reachable through a pushwall
+        for &(x, y) in &pushwalls {
+            if cells[y * width + x] == Reach::Unreachable {
+                cells[y * width + x] = Reach::SecretOnly;
+                queue.push_back((x, y));
+            }
+        }
+        Self::flood(
+            &mut cells,
+            width,
+            height,
+            &mut queue,
+            Reach::SecretOnly,
+            |x, y| walkable(x, y) || pushwall(x, y),
+        );
+
+        let mut unreachable = Vec::new();
+        let mut secret_only = Vec::new();
+        for spawn in map.spawns(difficulty) {
+            let relevant = match spawn.object {
+                ObjectSpawn::Static(kind) => kind.is_bonus(),
+                ObjectSpawn::Enemy { .. } => true,
+                _ => false,
+            };
+            match cells[spawn.y * width + spawn.x] {
+                Reach::Unreachable if relevant => unreachable.push(spawn),
+                Reach::SecretOnly if relevant => secret_only.push(spawn),
+                _ => {}
+            }
+        }
+
+        // The switch only works when it is pressed facing east or west
+        let mut exits = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if map.tile(x, y) != Some(Tile::Wall(ELEVATORTILE)) {
+                    continue;
+                }
+
+                let reach = [x.checked_sub(1), Some(x + 1).filter(|&x| x < width)]
+                    .into_iter()
+                    .flatten()
+                    .map(|x| cells[y * width + x])
+                    .fold(Reach::Unreachable, |best, reach| match (best, reach) {
+                        (Reach::Reachable, _) | (_, Reach::Reachable) => Reach::Reachable,
+                        (Reach::SecretOnly, _) | (_, Reach::SecretOnly) => Reach::SecretOnly,
+                        _ => Reach::Unreachable,
+                    });
+                exits.push((x, y, reach));
+            }
+        }
+
+        Some(Self {
+            width,
+            height,
+            cells,
+            unreachable,
+            secret_only,
+            exits,
+        })
+    }
+
+    fn flood(
+        cells: &mut [Reach],
+        width: usize,
+        height: usize,
+        queue: &mut VecDeque<(usize, usize)>,
+        reach: Reach,
+        mut passable: impl FnMut(usize, usize) -> bool,
+    ) {
+        while let Some((x, y)) = queue.pop_front() {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+
+            for (x, y) in neighbours {
+                if x >= width || y >= height || cells[y * width + x] != Reach::Unreachable {
+                    continue;
+                }
+                if passable(x, y) {
+                    cells[y * width + x] = reach;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+    }
+
+    pub fn reach(&self, x: usize, y: usize) -> Reach {
+        self.cells[y * self.width + x]
+    }
+
+    /// Whether any elevator switch can be pressed without going through a pushwall
+    pub fn exit_reachable(&self) -> bool {
+        self.exits
+            .iter()
+            .any(|&(_, _, reach)| reach == Reach::Reachable)
+    }
+}
+
+impl Gamemaps {
+    pub fn reachability(&self, difficulty: Difficulty) -> Option<Reachability> {
+        Reachability::new(self, difficulty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a map from rows of characters, `#` wall, `.` floor, `E` elevator switch,
+    /// `P` player start, `S` pushwall, `$` cross, `G` guard and `D` door
+    fn map_from_rows(rows: &[&str]) -> Gamemaps {
+        let width = rows[0].len();
+        let mut plane0 = Vec::new();
+        let mut plane1 = Vec::new();
+        for row in rows {
+            for c in row.chars() {
+                let (tile, object): (u16, u16) = match c {
+                    '#' => (1, 0),
+                    'E' => (ELEVATORTILE, 0),
+                    'S' => (1, 98),
+                    'D' => (90, 0),
+                    'P' => (107, 19),
+                    '$' => (107, 52),
+                    'G' => (107, 108),
+                    _ => (107, 0),
+                };
+                plane0.extend_from_slice(&tile.to_le_bytes());
+                plane1.extend_from_slice(&object.to_le_bytes());
+            }
+        }
+
+        Gamemaps {
+            index: 0,
+            plane2: vec![0; plane0.len()],
+            plane0,
+            plane1,
+            width: width as u16,
+            height: rows.len() as u16,
+            name: "Test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_reachability() {
+        let map = map_from_rows(&[
+            "##########",
+            "#P.D.#$#G#",
+            "#.###.S###",
+            "#.....E###",
+            "##########",
+        ]);
+
+        let reach = map.reachability(Difficulty::Hard).unwrap();
+        assert_eq!(reach.reach(4, 1), Reach::Reachable);
+        assert_eq!(reach.reach(6, 2), Reach::SecretOnly);
+        assert_eq!(reach.reach(6, 1), Reach::SecretOnly);
+        assert_eq!(reach.reach(8, 1), Reach::Unreachable);
+
+        assert_eq!(reach.secret_only.len(), 1);
+        assert_eq!((reach.secret_only[0].x, reach.secret_only[0].y), (6, 1));
+        assert_eq!(reach.unreachable.len(), 1);
+        assert_eq!((reach.unreachable[0].x, reach.unreachable[0].y), (8, 1));
+
+        assert_eq!(reach.exits, [(6, 3, Reach::Reachable)]);
+        assert!(reach.exit_reachable());
+    }
+
+    #[test]
+    fn test_exit_switch_needs_east_or_west_access() {
+        let map = map_from_rows(&["#####", "#P..#", "##E##"]);
+
+        let reach = map.reachability(Difficulty::Hard).unwrap();
+        assert_eq!(reach.exits, [(2, 2, Reach::Unreachable)]);
+        assert!(!reach.exit_reachable());
+    }
+}
@@ -1,13 +1,16 @@
+use crate::surface::Surface;
+
 const SIGNON: &[u8] = include_bytes!("../SIGNON.BIN");
 
-pub fn draw(output_buffer: &mut [u8]) {
-    let width = 320 / 4;
-    for plane in 0..4 {
-        for y in 0..200 {
-            for x in 0..width {
-                let offset = y * 320 + x + plane * width;
-                output_buffer[offset] = SIGNON[offset];
-            }
-        }
+/// The 320x200 screen shown while the game starts up
+pub fn surface() -> Surface {
+    Surface {
+        width: 320,
+        height: 200,
+        pixels: SIGNON.to_vec(),
     }
-}
\ No newline at end of file
+}
+
+pub fn draw(surface: &mut Surface, x: i32, y: i32) {
+    surface.draw(&self::surface(), x, y);
+}
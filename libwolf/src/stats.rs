@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    gamemaps::Gamemaps,
+    objects::{Difficulty, EnemyKind, ObjectSpawn},
+};
+
+/// Totals a level starts out with, the same the intermission screen ratios are based on
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LevelStats {
+    pub enemies: BTreeMap<EnemyKind, u32>,
+    /// Enemies counted for the kill ratio, every enemy including ghosts
+    pub kills: u32,
+    /// Items counted for the treasure ratio, which includes extra lives
+    pub treasures: u32,
+    pub treasure_points: u32,
+    /// Pushwalls counted for the secret ratio
+    pub secrets: u32,
+    /// Ammo lying around, not counting what enemies drop
+    pub ammo: u32,
+    pub health: u32,
+}
+
+impl LevelStats {
+    pub fn new(map: &Gamemaps, difficulty: Difficulty) -> Self {
+        let mut stats = Self::default();
+
+        for spawn in map.spawns(difficulty) {
+            match spawn.object {
+                ObjectSpawn::Enemy { kind, .. } => {
+                    *stats.enemies.entry(kind).or_default() += 1;
+                    stats.kills += 1;
+                }
+                ObjectSpawn::Static(kind) => {
+                    if let Some(points) = kind.treasure_points() {
+                        stats.treasures += 1;
+                        stats.treasure_points += points;
+                    }
+                    stats.ammo += kind.ammo();
+                    stats.health += kind.health();
+                }
+                ObjectSpawn::Pushwall => stats.secrets += 1,
+                _ => {}
+            }
+        }
+
+        stats
+    }
+}
+
+impl Gamemaps {
+    pub fn stats(&self, difficulty: Difficulty) -> LevelStats {
+        LevelStats::new(self, difficulty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_stats() {
+        let objects: [u16; 10] = [19, 52, 55, 56, 48, 49, 98, 108, 144, 224];
+        let mut plane1 = objects
+            .iter()
+            .flat_map(|code| code.to_le_bytes())
+            .collect::<Vec<_>>();
+        plane1.resize(64 * 64 * 2, 0);
+
+        let map = Gamemaps {
+            index: 0,
+            plane0: vec![0; 64 * 64 * 2],
+            plane1,
+            plane2: vec![0; 64 * 64 * 2],
+            width: 64,
+            height: 64,
+            name: "Test".to_string(),
+        };
+
+        let easy = map.stats(Difficulty::Easy);
+        assert_eq!(easy.kills, 2);
+        assert_eq!(easy.enemies[&EnemyKind::Guard], 1);
+        assert_eq!(easy.enemies[&EnemyKind::Blinky], 1);
+        assert_eq!(easy.treasures, 3);
+        assert_eq!(easy.treasure_points, 5100);
+        assert_eq!(easy.secrets, 1);
+        assert_eq!(easy.ammo, 25 + 8);
+        assert_eq!(easy.health, 99 + 25);
+
+        let medium = map.stats(Difficulty::Medium);
+        assert_eq!(medium.kills, 3);
+        assert_eq!(medium.enemies[&EnemyKind::Guard], 2);
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::{Read, Write};
+
+use thiserror::Error;
+
+use crate::{gr::MaskedTile, palette};
+
+#[derive(Debug, Error)]
+pub enum ImageError {
+    #[error(transparent)]
+    Png(#[from] png::DecodingError),
+    #[error("The PNG is not indexed")]
+    NotIndexed,
+}
+
+/// How [`Surface::draw_with`] copies pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawOptions {
+    /// Pixels of this colour are left out, for images using a colour key
+    pub transparent: Option<u8>,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+/// 8-bit image indexing into a 256 colour VGA palette, which everything else
+/// is drawn onto
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Surface {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Surface {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    /// Fills a rectangle, clipped to the image
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u8) {
+        for y in y..(y + height).min(self.height) {
+            let row = y * self.width;
+            for x in x..(x + width).min(self.width) {
+                self.pixels[row + x] = color;
+            }
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u8) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn fill(&mut self, color: u8) {
+        self.pixels.fill(color);
+    }
+
+    /// Copies `src` with its top left corner at `(x, y)`, which may lie
+    /// outside of the surface. Whatever doesn't fit is clipped.
+    pub fn draw(&mut self, src: &Surface, x: i32, y: i32) {
+        self.draw_with(src, x, y, DrawOptions::default());
+    }
+
+    pub fn draw_with(&mut self, src: &Surface, x: i32, y: i32, options: DrawOptions) {
+        self.blit(src, x, y, options, |_| true);
+    }
+
+    /// Like [`Surface::draw_with`], leaving out the pixels the mask of `tile`
+    /// marks transparent, e.g. wherever a sprite has no posts
+    pub fn draw_masked(&mut self, tile: &MaskedTile, x: i32, y: i32, options: DrawOptions) {
+        self.blit(&tile.image, x, y, options, |index| {
+            tile.transparent.get(index) == Some(&false)
+        });
+    }
+
+    /// Copies the pixels of `src` for whose index `opaque` holds
+    fn blit(
+        &mut self,
+        src: &Surface,
+        x: i32,
+        y: i32,
+        options: DrawOptions,
+        opaque: impl Fn(usize) -> bool,
+    ) {
+        // The part of `src` that lands on the surface
+        let left = (-x).clamp(0, src.width as i32) as usize;
+        let top = (-y).clamp(0, src.height as i32) as usize;
+        let right = (self.width as i32 - x).clamp(0, src.width as i32) as usize;
+        let bottom = (self.height as i32 - y).clamp(0, src.height as i32) as usize;
+
+        for src_y in top..bottom {
+            let dst_row = (y + src_y as i32) as usize * self.width;
+            let src_y = if options.flip_y {
+                src.height - 1 - src_y
+            } else {
+                src_y
+            };
+
+            for src_x in left..right {
+                let dst = dst_row + (x + src_x as i32) as usize;
+                let src_x = if options.flip_x {
+                    src.width - 1 - src_x
+                } else {
+                    src_x
+                };
+
+                let index = src_y * src.width + src_x;
+                let color = src.pixels[index];
+                if options.transparent != Some(color) && opaque(index) {
+                    self.pixels[dst] = color;
+                }
+            }
+        }
+    }
+
+    /// Expands to 8-bit RGBA using a 6-bit VGA palette such as [`crate::GAMEPAL`]
+    pub fn to_rgba(&self, palette: &[u8]) -> Vec<u8> {
+        let palette = vga_to_rgb8(palette);
+        self.pixels
+            .iter()
+            .flat_map(|&index| {
+                let rgb = &palette[index as usize * 3..index as usize * 3 + 3];
+                [rgb[0], rgb[1], rgb[2], 0xFF]
+            })
+            .collect()
+    }
+
+    /// Writes an indexed PNG using a 6-bit VGA palette such as [`crate::GAMEPAL`]
+    pub fn write_png<W: Write>(&self, writer: W, palette: &[u8]) -> std::io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(vga_to_rgb8(palette));
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Reads an indexed PNG, mapping each colour of its palette to the nearest
+    /// colour of a 6-bit VGA palette such as [`crate::GAMEPAL`]
+    pub fn read_png<R: Read>(reader: R, palette: &[u8]) -> Result<Self, ImageError> {
+        let mut reader = png::Decoder::new(reader).read_info()?;
+        let info = reader.info();
+        if info.color_type != png::ColorType::Indexed {
+            return Err(ImageError::NotIndexed);
+        }
+        let (width, height) = (info.width as usize, info.height as usize);
+        let depth = info.bit_depth as usize;
+        let png_palette = info.palette.as_deref().ok_or(ImageError::NotIndexed)?;
+
+        let palette = vga_to_rgb8(palette);
+        let remap: Vec<u8> = png_palette
+            .chunks_exact(3)
+            .map(|rgb| nearest_color(&palette, rgb))
+            .collect();
+
+        let mut data = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut data)?;
+
+        // Rows of less than 8 bits per pixel pack the leftmost pixel highest
+        let mut image = Self::new(width, height);
+        for (y, row) in data.chunks(frame.line_size).take(height).enumerate() {
+            for x in 0..width {
+                let bit = x * depth;
+                let index = (row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1) as u8;
+                image.pixels[y * width + x] = remap.get(index as usize).copied().unwrap_or(0);
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+/// The index of the colour of an 8-bit RGB palette closest to `rgb`
+fn nearest_color(palette: &[u8], rgb: &[u8]) -> u8 {
+    palette
+        .chunks_exact(3)
+        .map(|entry| {
+            entry
+                .iter()
+                .zip(rgb)
+                .map(|(&a, &b)| (a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        })
+        .enumerate()
+        .min_by_key(|&(_, distance)| distance)
+        .map_or(0, |(index, _)| index as u8)
+}
+
+fn vga_to_rgb8(palette: &[u8]) -> Vec<u8> {
+    palette.iter().map(|&v| palette::vga_to_rgb8(v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_clipped_and_flipped() {
+        let mut sprite = Surface::new(3, 2);
+        sprite.pixels.copy_from_slice(&[1, 2, 0xFF, 4, 5, 6]);
+
+        let mut surface = Surface::new(4, 3);
+        surface.fill(9);
+        surface.draw(&sprite, -1, 2);
+        assert_eq!(surface.pixels, [9, 9, 9, 9, 9, 9, 9, 9, 2, 0xFF, 9, 9]);
+
+        let options = DrawOptions {
+            transparent: Some(0xFF),
+            flip_x: true,
+            flip_y: true,
+        };
+        surface.fill(9);
+        surface.draw_with(&sprite, 2, 0, options);
+        assert_eq!(surface.pixels, [9, 9, 6, 5, 9, 9, 9, 2, 9, 9, 9, 9]);
+
+        // Entirely outside
+        surface.draw(&sprite, 4, -2);
+        surface.draw(&sprite, -3, 3);
+        assert_eq!(surface.pixels, [9, 9, 6, 5, 9, 9, 9, 2, 9, 9, 9, 9]);
+
+        let tile = MaskedTile {
+            image: sprite,
+            transparent: vec![false, true, false, true, false, false],
+        };
+        let options = DrawOptions {
+            flip_x: true,
+            ..Default::default()
+        };
+        surface.fill(9);
+        surface.draw_masked(&tile, 0, 0, options);
+        assert_eq!(surface.pixels, [0xFF, 9, 1, 9, 6, 5, 9, 9, 9, 9, 9, 9]);
+    }
+}
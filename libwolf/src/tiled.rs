@@ -0,0 +1,748 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+    path::Path,
+};
+
+use quick_xml::events::{BytesStart, Event};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{gamemaps::Gamemaps, surface::Surface};
+
+//
+// Each plane becomes a tile layer named plane0, plane1 and plane2. Code 0 is an empty cell,
+// any other code n is tile n - 1 of an image collection tileset, whose tiles point at
+// codes/<n>.png, see write_tile_images. Exported maps use firstgid 1, so GIDs equal
+// the plane codes.
+//
+const LAYER_NAMES: [&str; 3] = ["plane0", "plane1", "plane2"];
+const TILED_VERSION: &str = "1.10";
+const TILE_SIZE: u32 = 64;
+const TILESET_NAME: &str = "codes";
+const FIRST_GID: u32 = 1;
+
+// 3x5 digits for the tile images, one row per byte with the leftmost pixel in bit 2
+const DIGITS: [[u8; 5]; 10] = [
+    [7, 5, 5, 5, 7],
+    [2, 6, 2, 2, 7],
+    [7, 1, 7, 4, 7],
+    [7, 1, 7, 1, 7],
+    [5, 5, 7, 1, 1],
+    [7, 4, 7, 1, 7],
+    [7, 4, 7, 5, 7],
+    [7, 1, 1, 1, 1],
+    [7, 5, 7, 5, 7],
+    [7, 5, 7, 1, 7],
+];
+const DIGIT_SCALE: usize = 3;
+const DIGIT_COLOR: u8 = 15;
+
+#[derive(Debug, Error)]
+pub enum TiledError {
+    #[error("Invalid TMX: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("Invalid TMJ: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Missing attribute {0}")]
+    MissingAttribute(&'static str),
+    #[error("Invalid value for {0}")]
+    InvalidAttribute(&'static str),
+    #[error("Map size {width}x{height} does not fit in GAMEMAPS")]
+    InvalidDimensions { width: u32, height: u32 },
+    #[error("Property {name} is {found}, but the map is {expected}")]
+    DimensionsMismatch {
+        name: &'static str,
+        expected: u32,
+        found: u32,
+    },
+    #[error("Missing layer {0}")]
+    MissingLayer(&'static str),
+    #[error("Layer {layer} has {found} tiles, expected {expected}")]
+    LayerSizeMismatch {
+        layer: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Layer {layer} contains tile {gid}, which is not a plane code")]
+    InvalidTile { layer: &'static str, gid: u32 },
+    #[error("Layer encoding {0} is not supported, save the map with CSV layers")]
+    UnsupportedEncoding(String),
+}
+
+fn plane_words(plane: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    plane
+        .chunks_exact(2)
+        .map(|word| u16::from_le_bytes([word[0], word[1]]))
+}
+
+fn planes(map: &Gamemaps) -> [&[u8]; 3] {
+    [&map.plane0, &map.plane1, &map.plane2]
+}
+
+/// The codes other than 0 used anywhere in the map, each needs a tile
+fn used_codes(map: &Gamemaps) -> BTreeSet<u16> {
+    planes(map)
+        .into_iter()
+        .flat_map(plane_words)
+        .filter(|&code| code != 0)
+        .collect()
+}
+
+fn tile_image(code: u16) -> String {
+    format!("{}/{}.png", TILESET_NAME, code)
+}
+
+/// A tile showing `code` in white on black
+fn code_tile(code: u16) -> Surface {
+    let size = TILE_SIZE as usize;
+    let digits = code.to_string();
+    let mut tile = Surface::new(size, size);
+
+    // Digits are 3 pixels wide with a pixel between them, before scaling
+    let width = (digits.len() * 4 - 1) * DIGIT_SCALE;
+    let (left, top) = ((size - width) / 2, (size - 5 * DIGIT_SCALE) / 2);
+
+    for (i, digit) in digits.bytes().enumerate() {
+        for (y, row) in DIGITS[(digit - b'0') as usize].iter().enumerate() {
+            for x in (0..3).filter(|x| row & (4 >> x) != 0) {
+                tile.fill_rect(
+                    left + (i * 4 + x) * DIGIT_SCALE,
+                    top + y * DIGIT_SCALE,
+                    DIGIT_SCALE,
+                    DIGIT_SCALE,
+                    DIGIT_COLOR,
+                );
+            }
+        }
+    }
+
+    tile
+}
+
+/// Writes the images the exported tileset points at into `dir`. GAMEMAPS doesn't
+/// say which graphics a code stands for, so each image shows its plane code.
+pub fn write_tile_images(map: &Gamemaps, dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir.join(TILESET_NAME))?;
+
+    for code in used_codes(map) {
+        let file = std::fs::File::create(dir.join(tile_image(code)))?;
+        code_tile(code).write_png(std::io::BufWriter::new(file), crate::GAMEPAL)?;
+    }
+
+    Ok(())
+}
+
+fn escape_xml(text: &str) -> String {
+    quick_xml::escape::escape(text).into_owned()
+}
+
+/// Writes the map as a Tiled XML document
+pub fn to_tmx(map: &Gamemaps) -> String {
+    let mut tmx = String::new();
+
+    // Writing into a String can't fail
+    let _ = writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        tmx,
+        r#"<map version="{}" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="4" nextobjectid="1">"#,
+        TILED_VERSION, map.width, map.height, TILE_SIZE, TILE_SIZE
+    );
+    let _ = writeln!(tmx, " <properties>");
+    let _ = writeln!(
+        tmx,
+        r#"  <property name="name" value="{}"/>"#,
+        escape_xml(&map.name)
+    );
+    for (name, value) in [
+        ("index", map.index),
+        ("width", map.width as usize),
+        ("height", map.height as usize),
+    ] {
+        let _ = writeln!(
+            tmx,
+            r#"  <property name="{}" type="int" value="{}"/>"#,
+            name, value
+        );
+    }
+    let _ = writeln!(tmx, " </properties>");
+
+    let codes = used_codes(map);
+    let _ = writeln!(
+        tmx,
+        r#" <tileset firstgid="{}" name="{}" tilewidth="{}" tileheight="{}" tilecount="{}" columns="0">"#,
+        FIRST_GID,
+        TILESET_NAME,
+        TILE_SIZE,
+        TILE_SIZE,
+        codes.len()
+    );
+    let _ = writeln!(
+        tmx,
+        r#"  <grid orientation="orthogonal" width="1" height="1"/>"#
+    );
+    for code in codes {
+        let _ = writeln!(tmx, r#"  <tile id="{}">"#, code - 1);
+        let _ = writeln!(
+            tmx,
+            r#"   <image width="{}" height="{}" source="{}"/>"#,
+            TILE_SIZE,
+            TILE_SIZE,
+            tile_image(code)
+        );
+        let _ = writeln!(tmx, "  </tile>");
+    }
+    let _ = writeln!(tmx, " </tileset>");
+
+    for (id, (name, plane)) in LAYER_NAMES.iter().zip(planes(map)).enumerate() {
+        let _ = writeln!(
+            tmx,
+            r#" <layer id="{}" name="{}" width="{}" height="{}">"#,
+            id + 1,
+            name,
+            map.width,
+            map.height
+        );
+        let _ = writeln!(tmx, r#"  <data encoding="csv">"#);
+
+        let words = plane_words(plane).collect::<Vec<_>>();
+        let rows = words.chunks(map.width.max(1) as usize).collect::<Vec<_>>();
+        for (y, row) in rows.iter().enumerate() {
+            let row = row.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+            let separator = if y + 1 < rows.len() { "," } else { "" };
+            let _ = writeln!(tmx, "{}{}", row.join(","), separator);
+        }
+
+        let _ = writeln!(tmx, "</data>");
+        let _ = writeln!(tmx, " </layer>");
+    }
+
+    let _ = writeln!(tmx, "</map>");
+
+    tmx
+}
+
+fn attribute(element: &BytesStart, name: &'static str) -> Result<Option<String>, TiledError> {
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        if attribute.key.as_ref() == name.as_bytes() {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+fn parse_number(value: &str, name: &'static str) -> Result<u32, TiledError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| TiledError::InvalidAttribute(name))
+}
+
+/// Reads a map from a Tiled XML document with CSV encoded layers
+pub fn from_tmx(tmx: &str) -> Result<Gamemaps, TiledError> {
+    let mut reader = quick_xml::Reader::from_str(tmx);
+    reader.config_mut().trim_text(true);
+
+    let mut size = None;
+    let mut first_gid = None;
+    let mut properties = HashMap::new();
+    let mut layers = HashMap::new();
+    let mut current_layer = None;
+    let mut in_data = false;
+    // Names of the elements enclosing the current one
+    let mut parents: Vec<Vec<u8>> = Vec::new();
+
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(element) | Event::Empty(element) => {
+                // Tilesets and tiles have properties of their own
+                let map_property =
+                    parents.len() == 2 && parents[0] == b"map" && parents[1] == b"properties";
+
+                match element.name().as_ref() {
+                    b"map" => {
+                        let width = attribute(element, "width")?
+                            .ok_or(TiledError::MissingAttribute("width"))?;
+                        let height = attribute(element, "height")?
+                            .ok_or(TiledError::MissingAttribute("height"))?;
+                        size = Some((
+                            parse_number(&width, "width")?,
+                            parse_number(&height, "height")?,
+                        ));
+                    }
+                    // Only the first tileset holds plane codes
+                    b"tileset" if first_gid.is_none() => {
+                        let gid = attribute(element, "firstgid")?
+                            .ok_or(TiledError::MissingAttribute("firstgid"))?;
+                        first_gid = Some(parse_number(&gid, "firstgid")?);
+                    }
+                    b"property" if map_property => {
+                        let name = attribute(element, "name")?
+                            .ok_or(TiledError::MissingAttribute("name"))?;
+                        let value = attribute(element, "value")?.unwrap_or_default();
+                        properties.insert(name, value);
+                    }
+                    b"layer" => {
+                        current_layer = attribute(element, "name")?;
+                    }
+                    b"data" => {
+                        let encoding = attribute(element, "encoding")?.unwrap_or_default();
+                        if encoding != "csv" || attribute(element, "compression")?.is_some() {
+                            return Err(TiledError::UnsupportedEncoding(encoding));
+                        }
+                        in_data = true;
+                    }
+                    _ => {}
+                }
+
+                if let Event::Start(element) = &event {
+                    parents.push(element.name().as_ref().to_vec());
+                }
+            }
+            Event::Text(text) if in_data => {
+                if let Some(layer) = current_layer.clone() {
+                    let gids = text
+                        .unescape()?
+                        .split(',')
+                        .filter(|gid| !gid.trim().is_empty())
+                        .map(|gid| parse_number(gid, "data"))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    layers.insert(layer, gids);
+                }
+            }
+            Event::End(element) => {
+                parents.pop();
+                match element.name().as_ref() {
+                    b"data" => in_data = false,
+                    b"layer" => current_layer = None,
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let (width, height) = size.ok_or(TiledError::MissingAttribute("width"))?;
+    build_map(
+        width,
+        height,
+        first_gid.unwrap_or(FIRST_GID),
+        &properties,
+        layers,
+    )
+}
+
+#[derive(Serialize, Deserialize)]
+struct TmjMap {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    renderorder: String,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    nextlayerid: u32,
+    #[serde(default)]
+    nextobjectid: u32,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+    layers: Vec<TmjLayer>,
+    #[serde(default)]
+    tilesets: Vec<TmjTileset>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TmjTileset {
+    firstgid: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tiles: Vec<TmjTile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TmjTile {
+    id: u32,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    imagewidth: u32,
+    #[serde(default)]
+    imageheight: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TmjProperty {
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    value: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TmjLayer {
+    #[serde(default)]
+    id: u32,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    #[serde(default)]
+    x: i32,
+    #[serde(default)]
+    y: i32,
+    #[serde(default)]
+    opacity: f32,
+    #[serde(default)]
+    visible: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+/// Writes the map as a Tiled JSON document
+pub fn to_tmj(map: &Gamemaps) -> String {
+    let int_property = |name: &str, value: usize| TmjProperty {
+        name: name.to_string(),
+        kind: "int".to_string(),
+        value: value.into(),
+    };
+
+    let codes = used_codes(map);
+    let tmj = TmjMap {
+        kind: "map".to_string(),
+        version: TILED_VERSION.to_string(),
+        orientation: "orthogonal".to_string(),
+        renderorder: "right-down".to_string(),
+        width: map.width as u32,
+        height: map.height as u32,
+        tilewidth: TILE_SIZE,
+        tileheight: TILE_SIZE,
+        infinite: false,
+        nextlayerid: LAYER_NAMES.len() as u32 + 1,
+        nextobjectid: 1,
+        properties: vec![
+            TmjProperty {
+                name: "name".to_string(),
+                kind: "string".to_string(),
+                value: map.name.clone().into(),
+            },
+            int_property("index", map.index),
+            int_property("width", map.width as usize),
+            int_property("height", map.height as usize),
+        ],
+        layers: LAYER_NAMES
+            .iter()
+            .zip(planes(map))
+            .enumerate()
+            .map(|(id, (name, plane))| TmjLayer {
+                id: id as u32 + 1,
+                name: name.to_string(),
+                kind: "tilelayer".to_string(),
+                width: map.width as u32,
+                height: map.height as u32,
+                x: 0,
+                y: 0,
+                opacity: 1.0,
+                visible: true,
+                encoding: None,
+                data: plane_words(plane).collect::<Vec<_>>().into(),
+            })
+            .collect(),
+        tilesets: vec![TmjTileset {
+            firstgid: FIRST_GID,
+            name: TILESET_NAME.to_string(),
+            tilewidth: TILE_SIZE,
+            tileheight: TILE_SIZE,
+            tilecount: codes.len() as u32,
+            columns: 0,
+            tiles: codes
+                .iter()
+                .map(|&code| TmjTile {
+                    id: code as u32 - 1,
+                    image: tile_image(code),
+                    imagewidth: TILE_SIZE,
+                    imageheight: TILE_SIZE,
+                })
+                .collect(),
+        }],
+    };
+
+    // Plain data, so serializing can't fail
+    serde_json::to_string(&tmj).unwrap()
+}
+
+/// Reads a map from a Tiled JSON document with CSV encoded layers
+pub fn from_tmj(tmj: &str) -> Result<Gamemaps, TiledError> {
+    let tmj: TmjMap = serde_json::from_str(tmj)?;
+
+    let properties = tmj
+        .properties
+        .into_iter()
+        .map(|property| {
+            let value = match property.value {
+                serde_json::Value::String(value) => value,
+                value => value.to_string(),
+            };
+            (property.name, value)
+        })
+        .collect();
+
+    let mut layers = HashMap::new();
+    for layer in tmj.layers {
+        if layer.kind != "tilelayer" {
+            continue;
+        }
+        if let Some(encoding) = layer.encoding.filter(|encoding| encoding != "csv") {
+            return Err(TiledError::UnsupportedEncoding(encoding));
+        }
+        let gids: Vec<u32> = serde_json::from_value(layer.data)?;
+        layers.insert(layer.name, gids);
+    }
+
+    let first_gid = tmj
+        .tilesets
+        .first()
+        .map_or(FIRST_GID, |tileset| tileset.firstgid);
+    build_map(tmj.width, tmj.height, first_gid, &properties, layers)
+}
+
+fn build_map(
+    width: u32,
+    height: u32,
+    first_gid: u32,
+    properties: &HashMap<String, String>,
+    mut layers: HashMap<String, Vec<u32>>,
+) -> Result<Gamemaps, TiledError> {
+    let (Ok(map_width), Ok(map_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(TiledError::InvalidDimensions { width, height });
+    };
+    // Gamemaps::write keeps the expanded size of a plane in 16 bits
+    if width == 0 || height == 0 || width as usize * height as usize * 2 > u16::MAX as usize {
+        return Err(TiledError::InvalidDimensions { width, height });
+    }
+
+    for (name, expected) in [("width", width), ("height", height)] {
+        if let Some(value) = properties.get(name) {
+            let found = parse_number(value, name)?;
+            if found != expected {
+                return Err(TiledError::DimensionsMismatch {
+                    name,
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
+    let index = properties
+        .get("index")
+        .map(|index| parse_number(index, "index"))
+        .transpose()?
+        .unwrap_or(0) as usize;
+    let name = properties.get("name").cloned().unwrap_or_default();
+
+    let mut planes = Vec::new();
+    for layer in LAYER_NAMES {
+        let gids = layers
+            .remove(layer)
+            .ok_or(TiledError::MissingLayer(layer))?;
+
+        let expected = width as usize * height as usize;
+        if gids.len() != expected {
+            return Err(TiledError::LayerSizeMismatch {
+                layer,
+                expected,
+                found: gids.len(),
+            });
+        }
+
+        let mut plane = Vec::with_capacity(expected * 2);
+        for gid in gids {
+            // Flipped or rotated tiles have flag bits set and can't be stored
+            let code = match gid {
+                0 => Some(0),
+                gid => gid
+                    .checked_sub(first_gid)
+                    .and_then(|tile| u16::try_from(tile).ok()?.checked_add(1)),
+            };
+            let code = code.ok_or(TiledError::InvalidTile { layer, gid })?;
+            plane.extend_from_slice(&code.to_le_bytes());
+        }
+        planes.push(plane);
+    }
+
+    let [plane0, plane1, plane2]: [Vec<u8>; 3] = planes.try_into().unwrap();
+
+    Ok(Gamemaps {
+        index,
+        plane0,
+        plane1,
+        plane2,
+        width: map_width,
+        height: map_height,
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_map() -> Gamemaps {
+        let plane = |seed: u16| {
+            (0..12u16)
+                .flat_map(|i| (i * seed).to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+
+        Gamemaps {
+            index: 7,
+            plane0: plane(1),
+            plane1: plane(300),
+            plane2: plane(5000),
+            width: 4,
+            height: 3,
+            name: "Tunnels <4> & \"more\"".to_string(),
+        }
+    }
+
+    fn assert_same_map(a: &Gamemaps, b: &Gamemaps) {
+        assert_eq!(a.index, b.index);
+        assert_eq!(a.name, b.name);
+        assert_eq!((a.width, a.height), (b.width, b.height));
+        assert_eq!(a.plane0, b.plane0);
+        assert_eq!(a.plane1, b.plane1);
+        assert_eq!(a.plane2, b.plane2);
+    }
+
+    #[test]
+    fn test_tmx_round_trip() {
+        let map = test_map();
+        assert_same_map(&from_tmx(&to_tmx(&map)).unwrap(), &map);
+    }
+
+    #[test]
+    fn test_tmj_round_trip() {
+        let map = test_map();
+        assert_same_map(&from_tmj(&to_tmj(&map)).unwrap(), &map);
+    }
+
+    #[test]
+    fn test_tileset_is_exported() {
+        let map = test_map();
+
+        let tmx = to_tmx(&map);
+        assert!(tmx.contains(r#"<tileset firstgid="1" name="codes""#));
+        assert!(tmx.contains(r#"<tile id="299">"#));
+        assert!(tmx.contains(r#"source="codes/300.png""#));
+
+        let tmj: serde_json::Value = serde_json::from_str(&to_tmj(&map)).unwrap();
+        assert_eq!(tmj["tilesets"][0]["firstgid"], 1);
+        assert_eq!(tmj["tilesets"][0]["tiles"][0]["image"], "codes/1.png");
+
+        // GIDs below the tileset's firstgid don't refer to a plane code
+        let tmx = tmx.replace(r#"firstgid="1""#, r#"firstgid="2""#);
+        assert!(matches!(
+            from_tmx(&tmx),
+            Err(TiledError::InvalidTile {
+                layer: "plane0",
+                gid: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_tmx_validates_layers() {
+        let tmx = to_tmx(&test_map()).replace("0,1,2,3,", "0,1,2,");
+        assert!(matches!(
+            from_tmx(&tmx),
+            Err(TiledError::LayerSizeMismatch {
+                layer: "plane0",
+                expected: 12,
+                found: 11
+            })
+        ));
+
+        let tmx = to_tmx(&test_map()).replace(r#"name="plane2""#, r#"name="objects""#);
+        assert!(matches!(
+            from_tmx(&tmx),
+            Err(TiledError::MissingLayer("plane2"))
+        ));
+
+        let tmx = to_tmx(&test_map()).replace(r#"value="3""#, r#"value="64""#);
+        assert!(matches!(
+            from_tmx(&tmx),
+            Err(TiledError::DimensionsMismatch { name: "height", .. })
+        ));
+
+        // Planes over 65535 bytes can't be written back to GAMEMAPS
+        let mut map = test_map();
+        let plane = vec![0; 182 * 182 * 2];
+        (map.plane0, map.plane1, map.plane2) = (plane.clone(), plane.clone(), plane);
+        (map.width, map.height) = (182, 182);
+        assert!(matches!(
+            from_tmx(&to_tmx(&map)),
+            Err(TiledError::InvalidDimensions {
+                width: 182,
+                height: 182
+            })
+        ));
+
+        // Properties of tilesets and tiles aren't map properties
+        let tmx = to_tmx(&test_map()).replace(
+            "  </tile>",
+            r#"   <properties><property name="height" value="9"/></properties>
+  </tile>"#,
+        );
+        assert_same_map(&from_tmx(&tmx).unwrap(), &test_map());
+    }
+
+    #[test]
+    fn test_write_tile_images() {
+        let dir = std::env::temp_dir().join(format!("libwolf-tiled-{}", std::process::id()));
+        let map = test_map();
+        write_tile_images(&map, &dir).unwrap();
+
+        for code in used_codes(&map) {
+            let file = std::fs::File::open(dir.join(tile_image(code))).unwrap();
+            let tile = Surface::read_png(file, crate::GAMEPAL).unwrap();
+            assert_eq!((tile.width, tile.height), (64, 64));
+            assert_eq!(tile.pixels, code_tile(code).pixels);
+        }
+        assert!(!dir.join("codes/0.png").exists());
+
+        // Each pixel of a digit is 3x3, and 5 digit codes fit in a tile too
+        let lit = |tile: Surface| tile.pixels.iter().filter(|&&p| p != 0).count();
+        assert_eq!(lit(code_tile(1)), 8 * 9);
+        assert!(code_tile(55000).pixels.contains(&DIGIT_COLOR));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
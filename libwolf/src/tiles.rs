@@ -0,0 +1,110 @@
+//
+// Tile codes of plane 0, see SetupGameLevel in WL_GAME.C
+//
+pub const ELEVATORTILE: u16 = 21;
+pub const AMBUSHTILE: u16 = 106;
+pub const AREATILE: u16 = 107;
+pub const ALTELEVATORTILE: u16 = 107;
+pub const NUMAREAS: u16 = 37;
+
+const FIRSTDOORTILE: u16 = 90;
+const LASTDOORTILE: u16 = 101;
+const LASTWALLTILE: u16 = 63;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    /// Solid wall, textured with VSWAP pages `(n - 1) * 2` (light) and `(n - 1) * 2 + 1` (dark)
+    Wall(u16),
+    Door {
+        kind: DoorKind,
+        orientation: DoorOrientation,
+    },
+    /// Walkable floor, doors connect areas so sound and enemies can travel between them
+    Floor { area: u16 },
+    /// Floor where enemies standing on it don't react to sound
+    AmbushFloor,
+    /// Codes the original engine does not define
+    Unknown(u16),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorKind {
+    Normal,
+    GoldKey,
+    SilverKey,
+    Lock3,
+    Lock4,
+    Elevator,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorOrientation {
+    /// Slides along the y axis, so it is walked through east to west
+    Vertical,
+    /// Slides along the x axis, so it is walked through north to south
+    Horizontal,
+}
+
+impl Tile {
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            1..=LASTWALLTILE => Tile::Wall(code),
+            FIRSTDOORTILE..=LASTDOORTILE => {
+                let kind = match (code - FIRSTDOORTILE) / 2 {
+                    0 => DoorKind::Normal,
+                    1 => DoorKind::GoldKey,
+                    2 => DoorKind::SilverKey,
+                    3 => DoorKind::Lock3,
+                    4 => DoorKind::Lock4,
+                    _ => DoorKind::Elevator,
+                };
+                let orientation = if (code - FIRSTDOORTILE) & 1 == 0 {
+                    DoorOrientation::Vertical
+                } else {
+                    DoorOrientation::Horizontal
+                };
+                Tile::Door { kind, orientation }
+            }
+            AMBUSHTILE => Tile::AmbushFloor,
+            AREATILE.. if code < AREATILE + NUMAREAS => Tile::Floor {
+                area: code - AREATILE,
+            },
+            _ => Tile::Unknown(code),
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        matches!(self, Tile::Wall(_) | Tile::Unknown(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_tiles() {
+        assert_eq!(Tile::from_code(1), Tile::Wall(1));
+        assert_eq!(Tile::from_code(ELEVATORTILE), Tile::Wall(21));
+        assert_eq!(
+            Tile::from_code(92),
+            Tile::Door {
+                kind: DoorKind::GoldKey,
+                orientation: DoorOrientation::Vertical
+            }
+        );
+        assert_eq!(
+            Tile::from_code(101),
+            Tile::Door {
+                kind: DoorKind::Elevator,
+                orientation: DoorOrientation::Horizontal
+            }
+        );
+        assert_eq!(Tile::from_code(AMBUSHTILE), Tile::AmbushFloor);
+        assert_eq!(Tile::from_code(ALTELEVATORTILE), Tile::Floor { area: 0 });
+        assert_eq!(Tile::from_code(143), Tile::Floor { area: 36 });
+        assert_eq!(Tile::from_code(0), Tile::Unknown(0));
+        assert_eq!(Tile::from_code(64), Tile::Unknown(64));
+        assert_eq!(Tile::from_code(144), Tile::Unknown(144));
+    }
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use crate::grlayout::GrLayout;
+
+/// The games built on the Wolf3D engine, told apart by the extension of their data files
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameVariant {
+    /// Wolfenstein 3D shareware, .WL1
+    Wolf3dShareware,
+    /// Wolfenstein 3D registered, .WL6
+    Wolf3d,
+    /// Spear of Destiny, .SOD
+    Spear,
+    /// Spear of Destiny demo, .SDM
+    SpearDemo,
+    /// Spear of Destiny mission pack 2, Return to Danger, .SD2
+    ReturnToDanger,
+    /// Spear of Destiny mission pack 3, The Ultimate Challenge, .SD3
+    UltimateChallenge,
+}
+
+impl GameVariant {
+    /// Every variant, in the order [`GameVariant::detect`] tries them
+    pub const ALL: [GameVariant; 6] = [
+        GameVariant::Wolf3d,
+        GameVariant::Spear,
+        GameVariant::ReturnToDanger,
+        GameVariant::UltimateChallenge,
+        GameVariant::Wolf3dShareware,
+        GameVariant::SpearDemo,
+    ];
+
+    /// Picks the variant whose VSWAP is present in `path`. Full games win over
+    /// shareware and demos when a directory holds more than one.
+    pub fn detect(path: &str) -> Option<Self> {
+        Self::detect_with(|name| Path::new(&format!("{}/{}", path, name)).exists())
+    }
+
+    /// Like [`GameVariant::detect`], with `exists` telling whether a file of the
+    /// given upper case name is present
+    pub fn detect_with(exists: impl Fn(&str) -> bool) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|variant| exists(&variant.file_name("VSWAP")))
+    }
+
+    /// Extension of the map and page files, e.g. `WL6`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GameVariant::Wolf3dShareware => "WL1",
+            GameVariant::Wolf3d => "WL6",
+            GameVariant::Spear => "SOD",
+            GameVariant::SpearDemo => "SDM",
+            GameVariant::ReturnToDanger => "SD2",
+            GameVariant::UltimateChallenge => "SD3",
+        }
+    }
+
+    /// Extension of VGADICT, VGAHEAD and VGAGRAPH. The mission packs only
+    /// replace maps and pages and use the graphics of Spear of Destiny.
+    pub fn graphics_extension(&self) -> &'static str {
+        match self {
+            GameVariant::ReturnToDanger | GameVariant::UltimateChallenge => "SOD",
+            _ => self.extension(),
+        }
+    }
+
+    /// Extension of AUDIOHED and AUDIOT, shared with Spear of Destiny like the graphics
+    pub fn audio_extension(&self) -> &'static str {
+        self.graphics_extension()
+    }
+
+    /// Every data file the engine loads for this variant
+    pub fn data_files(&self) -> Vec<String> {
+        let graphics = ["VGADICT", "VGAHEAD", "VGAGRAPH"]
+            .map(|name| format!("{}.{}", name, self.graphics_extension()));
+        let audio =
+            ["AUDIOHED", "AUDIOT"].map(|name| format!("{}.{}", name, self.audio_extension()));
+
+        ["MAPHEAD", "GAMEMAPS", "VSWAP"]
+            .map(|name| self.file_name(name))
+            .into_iter()
+            .chain(graphics)
+            .chain(audio)
+            .collect()
+    }
+
+    /// `name` with the extension of this variant's map and page files
+    pub fn file_name(&self, name: &str) -> String {
+        format!("{}.{}", name, self.extension())
+    }
+
+    pub fn is_spear(&self) -> bool {
+        !matches!(self, GameVariant::Wolf3dShareware | GameVariant::Wolf3d)
+    }
+
+    pub fn gr_layout(&self) -> GrLayout {
+        match self {
+            GameVariant::Wolf3dShareware | GameVariant::Wolf3d => GrLayout::WL6,
+            GameVariant::SpearDemo => GrLayout::SDM,
+            GameVariant::Spear | GameVariant::ReturnToDanger | GameVariant::UltimateChallenge => {
+                GrLayout::SOD
+            }
+        }
+    }
+
+    /// First music chunk in AUDIOT, after the PC speaker, AdLib and digitized sound chunks
+    pub fn music_start(&self) -> usize {
+        if self.is_spear() {
+            243
+        } else {
+            261
+        }
+    }
+
+    /// The 6 bit VGA game palette. Spear of Destiny only keeps its palette
+    /// inside the executable, so there is none to hand out for its variants.
+    pub fn palette(&self) -> Option<&'static [u8]> {
+        if self.is_spear() {
+            None
+        } else {
+            Some(crate::GAMEPAL)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let dir = std::env::temp_dir().join(format!("libwolf-variant-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_str().unwrap();
+
+        assert_eq!(GameVariant::detect(path), None);
+
+        std::fs::write(dir.join("VSWAP.SDM"), []).unwrap();
+        assert_eq!(GameVariant::detect(path), Some(GameVariant::SpearDemo));
+
+        std::fs::write(dir.join("VSWAP.SD2"), []).unwrap();
+        let variant = GameVariant::detect(path).unwrap();
+        assert_eq!(variant, GameVariant::ReturnToDanger);
+        assert_eq!(variant.file_name("MAPHEAD"), "MAPHEAD.SD2");
+        assert_eq!(variant.graphics_extension(), "SOD");
+        assert_eq!(variant.music_start(), 243);
+        assert_eq!(variant.gr_layout(), GrLayout::SOD);
+        assert_eq!(GameVariant::SpearDemo.gr_layout(), GrLayout::SDM);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
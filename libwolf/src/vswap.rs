@@ -2,6 +2,13 @@ use std::io::{Cursor, Read, Seek};
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
+use crate::{
+    datasource::DataSource,
+    gr::MaskedTile,
+    surface::{DrawOptions, Surface},
+    variant::GameVariant,
+};
+
 pub struct PCMInfo {
     pub chunk_start_index: u16,
     pub length: u16,
@@ -14,6 +21,10 @@ pub struct VSWAPArchive {
 }
 
 impl VSWAPArchive {
+    pub fn from_source(source: &dyn DataSource, variant: GameVariant) -> std::io::Result<Self> {
+        Self::open(&mut source.open(&variant.file_name("VSWAP"))?)
+    }
+
     pub fn open<R: Read + Seek>(reader: &mut R) -> std::io::Result<Self> {
         let chunks_in_file = reader.read_i16::<LittleEndian>()? as usize;
         let pm_sprite_start = reader.read_i16::<LittleEndian>()? as usize;
@@ -108,53 +119,165 @@ impl VSWAPArchive {
         })
     }
 
-    pub fn rasterize_wall(&self, wall_num: usize, output_buffer: &mut [u8]) {
+    /// Wall `wall_num` as a 64x64 surface. The walls are stored column by column.
+    pub fn wall(&self, wall_num: usize) -> Surface {
         let wall_data: &[u8] = &self.wall_chunks[wall_num];
+        let mut surface = Surface::new(64, 64);
 
         for x in 0..64 {
             for y in 0..64 {
-                output_buffer[y * 320 + x] = wall_data[x * 64 + y];
+                surface.set_pixel(x, y, wall_data[x * 64 + y]);
             }
         }
+
+        surface
+    }
+
+    pub fn rasterize_wall(&self, wall_num: usize, surface: &mut Surface, x: i32, y: i32) {
+        surface.draw(&self.wall(wall_num), x, y);
     }
 
-    pub fn rasterize_sprite(&self, sprite_num: usize, output_buffer: &mut [u8]) {
-        let sprite_data: &[u8] = &self.sprite_chunks[sprite_num];
+    /// Sprite `sprite_num` as a 64x64 image, with every pixel no post covers
+    /// marked transparent. Any colour may be used by the sprite itself.
+    pub fn sprite(&self, sprite_num: usize) -> std::io::Result<MaskedTile> {
+        let invalid = |message: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Sprite {}: {}", sprite_num, message),
+            )
+        };
+
+        let sprite_data: &[u8] = self
+            .sprite_chunks
+            .get(sprite_num)
+            .ok_or_else(|| invalid("there is no such sprite"))?;
         let mut sprite_reader = Cursor::new(&sprite_data);
 
-        let left_pix = sprite_reader.read_u16::<LittleEndian>().unwrap();
-        let right_pix = sprite_reader.read_u16::<LittleEndian>().unwrap();
+        let mut tile = MaskedTile {
+            image: Surface::new(64, 64),
+            transparent: vec![true; 64 * 64],
+        };
+
+        let left_pix = sprite_reader.read_u16::<LittleEndian>()?;
+        let right_pix = sprite_reader.read_u16::<LittleEndian>()?;
 
+        if right_pix < left_pix || right_pix >= 64 {
+            return Err(invalid("the columns are out of range"));
+        }
         let num_columns = right_pix - left_pix + 1;
         let column_offsets = (0..num_columns)
-            .map(|_| sprite_reader.read_u16::<LittleEndian>().unwrap())
-            .collect::<Vec<_>>();
+            .map(|_| sprite_reader.read_u16::<LittleEndian>())
+            .collect::<std::io::Result<Vec<_>>>()?;
 
-        let mut pixel_offset = sprite_reader.position();
+        let mut pixel_offset = sprite_reader.position() as usize;
 
-        for x in 0..num_columns {
-            sprite_reader.set_position(column_offsets[x as usize] as u64);
+        for (x, &column_offset) in (left_pix as usize..).zip(&column_offsets) {
+            sprite_reader.set_position(column_offset as u64);
 
             loop {
-                let mut ending_row = sprite_reader.read_u16::<LittleEndian>().unwrap();
+                let mut ending_row = sprite_reader.read_u16::<LittleEndian>()?;
                 if ending_row == 0 {
                     // 0 signals the end of a column
                     break;
                 }
 
-                let _ = sprite_reader.read_u16::<LittleEndian>().unwrap(); // Skip two bytes, don't know what they are
-                let mut starting_row = sprite_reader.read_u16::<LittleEndian>().unwrap();
+                let _ = sprite_reader.read_u16::<LittleEndian>()?; // Skip two bytes, don't know what they are
+                let mut starting_row = sprite_reader.read_u16::<LittleEndian>()?;
 
                 // I don't know why these are double the size of the actual rows
                 ending_row >>= 1;
                 starting_row >>= 1;
 
-                for y in starting_row..ending_row {
-                    output_buffer[y as usize * 320 + x as usize] = sprite_data[pixel_offset as usize];
+                if ending_row > 64 {
+                    return Err(invalid("a post runs past the last row"));
+                }
+                for y in starting_row as usize..ending_row as usize {
+                    let color = *sprite_data
+                        .get(pixel_offset)
+                        .ok_or_else(|| invalid("a post runs past the end of the chunk"))?;
+                    tile.image.pixels[y * 64 + x] = color;
+                    tile.transparent[y * 64 + x] = false;
 
                     pixel_offset += 1;
                 }
             }
         }
+
+        Ok(tile)
+    }
+
+    /// Draws sprite `sprite_num` with its top left corner at `(x, y)`,
+    /// clipped to the surface and leaving out what no post covers
+    pub fn rasterize_sprite(
+        &self,
+        sprite_num: usize,
+        surface: &mut Surface,
+        x: i32,
+        y: i32,
+    ) -> std::io::Result<()> {
+        self.rasterize_sprite_with(sprite_num, surface, x, y, DrawOptions::default())
+    }
+
+    /// Like [`VSWAPArchive::rasterize_sprite`], flipped as `options` ask for
+    pub fn rasterize_sprite_with(
+        &self,
+        sprite_num: usize,
+        surface: &mut Surface,
+        x: i32,
+        y: i32,
+        options: DrawOptions,
+    ) -> std::io::Result<()> {
+        surface.draw_masked(&self.sprite(sprite_num)?, x, y, options);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sprite_mask() {
+        // Column 1 holds a post covering rows 0 and 1, whose first pixel is colour 0xFF
+        let sprite = vec![1, 0, 1, 0, 8, 0, 0xFF, 7, 4, 0, 0, 0, 0, 0, 0, 0];
+        let archive = VSWAPArchive {
+            wall_chunks: Vec::new(),
+            sprite_chunks: vec![sprite.clone()],
+            raw_pcm_chunks: Vec::new(),
+        };
+
+        let tile = archive.sprite(0).unwrap();
+        assert_eq!(tile.image.pixels[1], 0xFF);
+        assert_eq!(tile.image.pixels[64 + 1], 7);
+        assert_eq!(tile.transparent.iter().filter(|&&t| !t).count(), 2);
+        assert!(!tile.transparent[1] && tile.transparent[0]);
+
+        let mut surface = Surface::new(2, 2);
+        surface.fill(3);
+        archive
+            .rasterize_sprite_with(
+                0,
+                &mut surface,
+                -61,
+                0,
+                DrawOptions {
+                    flip_x: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(surface.pixels, [3, 0xFF, 3, 7]);
+        archive.rasterize_sprite(0, &mut surface, -1, 0).unwrap();
+        assert_eq!(surface.pixels, [0xFF, 0xFF, 7, 7]);
+
+        // Columns past the data or a post whose pixels are missing
+        let broken = VSWAPArchive {
+            wall_chunks: Vec::new(),
+            sprite_chunks: vec![vec![1, 0, 2, 0, 8, 0], sprite[..7].to_vec()],
+            raw_pcm_chunks: Vec::new(),
+        };
+        assert!(broken.sprite(0).is_err());
+        assert!(broken.sprite(1).is_err());
+        assert!(broken.sprite(2).is_err());
     }
 }
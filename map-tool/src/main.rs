@@ -0,0 +1,137 @@
+use std::{env::args, fmt::Display, fs::File, io::BufWriter, process::exit};
+
+use libwolf::{
+    automap, gamedata::GameData, gamemaps::Gamemaps, objects::Difficulty, palette::Palette,
+};
+
+const USAGE: &str =
+    "usage: map-tool <report|lint|render> <wolf3d path> [output path] [palette file]";
+
+/// Prints `error` to stderr and exits with a failure status
+fn fail(error: impl Display) -> ! {
+    eprintln!("map-tool: {}", error);
+    exit(1)
+}
+
+fn load_maps(game_data: &mut GameData) -> Vec<Gamemaps> {
+    game_data
+        .maps()
+        .and_then(|maps| Ok(maps.load_all()?))
+        .unwrap_or_else(|e| fail(e))
+}
+
+fn report(game_data: &mut GameData) {
+    let maps = load_maps(game_data);
+
+    println!(
+        "{:<4} {:<16} {:<6} {:>5} {:>9} {:>7} {:>7} {:>5} {:>6}  Enemies",
+        "Slot", "Name", "Diff", "Kills", "Treasure", "Points", "Secrets", "Ammo", "Health"
+    );
+
+    for map in &maps {
+        for difficulty in Difficulty::ALL {
+            let stats = map.stats(difficulty);
+            let enemies = stats
+                .enemies
+                .iter()
+                .map(|(kind, count)| format!("{}x{:?}", count, kind))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            println!(
+                "{:<4} {:<16} {:<6} {:>5} {:>9} {:>7} {:>7} {:>5} {:>6}  {}",
+                map.index,
+                map.name,
+                format!("{:?}", difficulty),
+                stats.kills,
+                stats.treasures,
+                stats.treasure_points,
+                stats.secrets,
+                stats.ammo,
+                stats.health,
+                enemies
+            );
+        }
+    }
+}
+
+fn lint(game_data: &mut GameData) {
+    let maps = load_maps(game_data);
+
+    for map in &maps {
+        for diagnostic in map.lint() {
+            println!("{} ({}): {}", map.name, map.index, diagnostic);
+        }
+    }
+}
+
+/// Reads a JASC-PAL or raw 768 byte `.pal` file
+fn read_palette(path: &str) -> Palette {
+    let data = std::fs::read(path).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+    let palette = if data.starts_with(b"JASC-PAL") {
+        Palette::from_jasc(&String::from_utf8_lossy(&data))
+    } else {
+        Palette::from_pal(&data)
+    };
+    palette.unwrap_or_else(|e| fail(format!("{}: {}", path, e)))
+}
+
+fn render(game_data: &mut GameData, output_path: &str, palette_path: Option<&str>) {
+    let maps = load_maps(game_data);
+
+    // Spear of Destiny keeps its palette in the executable, it has to be given
+    let palette = match palette_path {
+        Some(path) => read_palette(path),
+        None => Palette::for_variant(game_data.variant()).unwrap_or_else(|| {
+            fail(format!(
+                "{:?} has no built-in palette, pass a palette file",
+                game_data.variant()
+            ))
+        }),
+    };
+
+    let variant = game_data.variant();
+
+    // Without VSWAP the walls are drawn in a flat colour
+    let vswap = game_data.pages().ok();
+
+    for map in &maps {
+        let image = automap::render_map(map, vswap, 16, Difficulty::Hard);
+        // Only Wolfenstein 3D has episodes of ten maps each
+        let filename = if variant.is_spear() {
+            format!("{}/MAP{:02}.png", output_path, map.index)
+        } else {
+            format!(
+                "{}/E{}M{}.png",
+                output_path,
+                map.index / 10 + 1,
+                map.index % 10 + 1
+            )
+        };
+
+        let writer = BufWriter::new(
+            File::create(&filename).unwrap_or_else(|e| fail(format!("{}: {}", filename, e))),
+        );
+        image
+            .write_png(writer, palette.as_ref())
+            .unwrap_or_else(|e| fail(format!("{}: {}", filename, e)));
+        println!("{}: {}", filename, map.name);
+    }
+}
+
+fn main() {
+    let command = args().nth(1).unwrap_or_else(|| fail(USAGE));
+    let wolf_path = args().nth(2).unwrap_or_else(|| fail(USAGE));
+    let mut game_data = GameData::open(&wolf_path).unwrap_or_else(|e| fail(e));
+
+    match command.as_str() {
+        "report" => report(&mut game_data),
+        "lint" => lint(&mut game_data),
+        "render" => render(
+            &mut game_data,
+            &args().nth(3).unwrap_or(".".to_string()),
+            args().nth(4).as_deref(),
+        ),
+        _ => fail(USAGE),
+    }
+}
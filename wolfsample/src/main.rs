@@ -1,55 +1,6 @@
-use libwolf::{gr, vswap::VSWAPArchive, wl6_igrab};
+use libwolf::{gamedata::GameData, palette::Palette, surface::Surface, wl6_igrab};
 use minifb::{Key, Window, WindowOptions};
-use std::{env::args, fs::File, io::BufReader};
-
-// fn blit(
-//     src_data: &[u8],
-//     src_width: u16,
-//     src_height: u16,
-//     dest_x: usize,
-//     dest_y: usize,
-//     dest_data: &mut [u32],
-//     palette: &[u32],
-// ) {
-//     for j in 0..src_height as usize {
-//         for i in 0..src_width as usize {
-//             let color_index = src_data[j * src_width as usize + i] as usize;
-//             dest_data[(dest_y + j) * 320 + dest_x + i] = palette[color_index];
-//         }
-//     }
-// }
-
-struct ScratchBuffer<'a> {
-    data: Vec<u8>,
-    palette: &'a [u32],
-}
-
-impl<'a> ScratchBuffer<'a> {
-    pub fn new(palette: &'a [u32]) -> Self {
-        ScratchBuffer {
-            data: vec![0; 320 * 200],
-            palette,
-        }
-    }
-
-    pub fn blit(
-        &self,
-        src_width: u16,
-        src_height: u16,
-        dest_data: &mut [u32],
-        dest_x: usize,
-        dest_y: usize,
-        rotate: bool,
-    ) {
-        for y in 0..src_height as usize {
-            for x in 0..src_width as usize {
-                let (x, y) = if rotate { (y, x) } else { (x, y) };
-                let color_index = self.data[y * 320 + x] as usize;
-                dest_data[(dest_y + y) * 320 + dest_x + x] = self.palette[color_index];
-            }
-        }
-    }
-}
+use std::env::args;
 
 fn main() {
     let asset_number = args()
@@ -58,49 +9,39 @@ fn main() {
         .parse::<usize>()
         .expect("<music number> must be a number");
 
-    let mut palette_u32 = vec![0; 256];
-    let brightness = 2;
+    let palette_u32 = Palette::game().to_u32();
 
-    for i in 0..256 {
-        let r = libwolf::GAMEPAL[i * 3] as u32;
-        let g = libwolf::GAMEPAL[i * 3 + 1] as u32;
-        let b = libwolf::GAMEPAL[i * 3 + 2] as u32;
+    let mut screen = Surface::new(320, 200);
 
-        palette_u32[i] = (r << brightness << 16) | (g << brightness << 8) | b << brightness;
-    }
+    libwolf::signon::draw(&mut screen, 0, 0);
 
-    let mut screen_buffer_u32: Vec<u32> = vec![0; 320 * 200];
-
-    let mut scratch_buffer = ScratchBuffer::new(&palette_u32);
+    let wolf_base_path = r"c:\classic\wolf3d";
+    let mut game_data = GameData::open(wolf_base_path).unwrap();
 
-    libwolf::signon::draw(&mut scratch_buffer.data);
-    scratch_buffer.blit(320, 200, &mut screen_buffer_u32, 0, 0, false);
+    let output_sample_rate = 44100;
+    let num_streaming_buffers = 4;
+    let music_buffer_size = 12000;
+    let num_channels = 2; // Stereo
 
-    let wolf_base_path = r"c:\classic\wolf3d";
+    let mut imf = game_data.music(asset_number, output_sample_rate).unwrap();
 
-    let mut gr = gr::GrArchive::new(wolf_base_path);
+    let gr = game_data.graphics().unwrap();
 
     let pic = gr.load_pic(wl6_igrab::GraphicNum::L_BJWINSPIC).unwrap();
-    pic.draw(&mut scratch_buffer.data);
-    scratch_buffer.blit(pic.size.width, pic.size.height, &mut screen_buffer_u32, 200, 50, false);
+    pic.draw(&mut screen, 200, 50);
 
-    let mut reader = BufReader::new(File::open(format!("{}/vswap.wl6", wolf_base_path)).unwrap());
-    let vswap = VSWAPArchive::open(&mut reader).unwrap();
+    let vswap = game_data.pages().unwrap();
 
     let mut current_sprite = 0;
 
-    vswap.rasterize_wall(18, &mut scratch_buffer.data);
-    scratch_buffer.blit(64, 64, &mut screen_buffer_u32, 0, 0, true);
-
-    vswap.rasterize_sprite(54, &mut scratch_buffer.data);
-    scratch_buffer.blit(64, 64, &mut screen_buffer_u32, 0, 0, false);
-
-    let output_sample_rate = 44100;
-    let num_streaming_buffers = 4;
-    let music_buffer_size = 12000;
-    let num_channels = 2; // Stereo
+    vswap.rasterize_wall(18, &mut screen, 0, 0);
+    vswap.rasterize_sprite(54, &mut screen, 0, 0).unwrap();
 
-    let mut imf = libwolf::imf::Imf::new(wolf_base_path, asset_number, output_sample_rate).unwrap();
+    let screen_buffer_u32: Vec<u32> = screen
+        .pixels
+        .iter()
+        .map(|&color_index| palette_u32[color_index as usize])
+        .collect();
 
     let mut mixer = mixer::Mixer::new(num_streaming_buffers);
     let mut music_buffer: Vec<i16> = vec![0; music_buffer_size * num_channels as usize];
@@ -131,12 +72,12 @@ fn main() {
             && current_sprite < vswap.sprite_chunks.len() - 1
         {
             current_sprite += 1;
-            // screen_buffer_u8.fill(0);
-            // vswap.rasterize_sprite(current_sprite, &mut screen_buffer_u8);
+            // screen.fill(0);
+            // vswap.rasterize_sprite(current_sprite, &mut screen, 0, 0).unwrap();
         } else if window.is_key_pressed(Key::Left, minifb::KeyRepeat::Yes) && current_sprite > 0 {
             current_sprite -= 1;
-            // screen_buffer_u8.fill(0);
-            // vswap.rasterize_sprite(current_sprite, &mut screen_buffer_u8);
+            // screen.fill(0);
+            // vswap.rasterize_sprite(current_sprite, &mut screen, 0, 0).unwrap();
         }
 
         window